@@ -1,12 +1,19 @@
+#![allow(clippy::needless_return)]
+
 extern crate core;
 
 use std::io;
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
 use std::path::{PathBuf};
-use neural::{CostFunction, Network, layer, ActivationFunction, Float};
-use neural_utils::{io::read_idx_file, outputs_from_labels};
+use neural::{CostFunction, Network, layer, ActivationFunction, Float, Trainer, TrainingOptions};
+use neural::trainer::{Callback, EpochResult};
+use neural_utils::{idx_preprocessing, io::read_idx_file, outputs_from_labels};
+use neural_utils::preset::Preset;
 
-const BATCH_SIZE: usize = 10;
+const BATCH_SIZE: NonZeroUsize = NonZeroUsize::new(10).unwrap();
 const LEARNING_RATE: Float = 0.01;
+const PRESET: Preset = Preset::EmnistLetters;
 
 fn test(network: &Network, test_data: &[(Vec<Float>, u8)]) -> Float {
     let mut result: Float = 0 as Float;
@@ -29,74 +36,102 @@ fn test(network: &Network, test_data: &[(Vec<Float>, u8)]) -> Float {
     return result / test_data.len() as Float;
 }
 
+/// Writes the accuracy on the training and test data and the losses as a CSV row after every epoch
+struct CsvLogger {
+    writer: csv::Writer<io::Stdout>,
+    train_test_data: Vec<(Vec<Float>, u8)>,
+    test_data: Vec<(Vec<Float>, u8)>,
+}
+
+impl Callback for CsvLogger {
+    fn on_epoch_end(&mut self, network: &Network, result: &EpochResult) -> ControlFlow<()> {
+        let train_result = test(network, &self.train_test_data);
+        let test_result = test(network, &self.test_data);
+
+        let written = self
+            .writer
+            .write_record([format!("{}", result.epoch + 1), format!("{}", train_result), format!("{}", test_result), format!("{}", result.loss), format_optional(result.validation_loss)])
+            .and_then(|_| Ok(self.writer.flush()?));
+
+        match written {
+            Ok(_) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+fn format_optional(value: Option<Float>) -> String {
+    return value.map(|x| format!("{}", x)).unwrap_or_default();
+}
+
 fn epochs() -> Result<(), Box<dyn std::error::Error>> {
     let mut network = Network::new(CostFunction::MeanSquaredError);
 
-    network.add_layer(layer::Input::new(28 * 28));
-    network.add_layer(layer::FullyConnected::new(28 * 28, 392, ActivationFunction::ReLU));
-    network.add_layer(layer::FullyConnected::new(392, 196, ActivationFunction::ReLU));
-    network.add_layer(layer::FullyConnected::new(196, 98, ActivationFunction::ReLU));
-    network.add_layer(layer::FullyConnected::new(98, 49, ActivationFunction::ReLU));
-    network.add_layer(layer::FullyConnected::new(49, 27, ActivationFunction::Sigmoid));
+    network.add_layer(layer::Input::new(28 * 28))?;
+    network.add_layer(layer::FullyConnected::new(28 * 28, 392, ActivationFunction::ReLU))?;
+    network.add_layer(layer::FullyConnected::new(392, 196, ActivationFunction::ReLU))?;
+    network.add_layer(layer::FullyConnected::new(196, 98, ActivationFunction::ReLU))?;
+    network.add_layer(layer::FullyConnected::new(98, 49, ActivationFunction::ReLU))?;
+    network.add_layer(layer::FullyConnected::new(49, PRESET.class_count(), ActivationFunction::Sigmoid))?;
+
+    network.preprocessing = idx_preprocessing();
 
     let training_images = read_idx_file(&PathBuf::from("./data/emnist/letters/train-images"))?;
-    let training_labels = read_idx_file(&PathBuf::from("./data/emnist/letters/train-labels"))?;
+    let training_labels = PRESET.labels(read_idx_file(&PathBuf::from("./data/emnist/letters/train-labels"))?.items)?;
     let test_images = read_idx_file(&PathBuf::from("./data/emnist/letters/test-images"))?;
-    let test_labels = read_idx_file(&PathBuf::from("./data/emnist/letters/test-labels"))?;
+    let test_labels = PRESET.labels(read_idx_file(&PathBuf::from("./data/emnist/letters/test-labels"))?.items)?;
 
-    let training_data: Vec<(Vec<Float>, Vec<Float>)> = training_images.items.clone()
+    let inputs = |images: Vec<Vec<u8>>| -> Vec<Vec<Float>> {
+        PRESET.inputs(images)
+            .into_iter()
+            .map(|input| network.preprocessing.transform(input))
+            .collect()
+    };
+
+    let training_data: Vec<(Vec<Float>, Vec<Float>)> = inputs(training_images.items.clone())
         .into_iter()
-        .map(|input| {
-            input
-                .into_iter()
-                .map(|x| Float::from(x) / 255.0)
-                .collect::<Vec<_>>()
-        })
-        .zip(outputs_from_labels(&network, training_labels.items.clone()).into_iter())
+        .zip(outputs_from_labels(&network, training_labels.clone()))
         .collect();
 
-    let train_test_data: Vec<(Vec<Float>, u8)> = training_images.items
+    let train_test_data: Vec<(Vec<Float>, u8)> = inputs(training_images.items)
         .into_iter()
-        .map(|input| {
-            input
-                .into_iter()
-                .map(|x| Float::from(x) / 255.0)
-                .collect::<Vec<_>>()
-        })
-        .zip(training_labels.items.into_iter().map(|x| x[0]))
+        .zip(training_labels.into_iter().map(|x| x[0]))
         .collect();
 
-    let test_data: Vec<(Vec<Float>, u8)> = test_images.items
+    let test_loss_data: Vec<(Vec<Float>, Vec<Float>)> = inputs(test_images.items.clone())
         .into_iter()
-        .map(|input| {
-            input
-                .into_iter()
-                .map(|x| Float::from(x) / 255.0)
-                .collect::<Vec<_>>()
-        })
-        .zip(test_labels.items.into_iter().map(|x| x[0]))
+        .zip(outputs_from_labels(&network, test_labels.clone()))
+        .collect();
+
+    let test_data: Vec<(Vec<Float>, u8)> = inputs(test_images.items)
+        .into_iter()
+        .zip(test_labels.into_iter().map(|x| x[0]))
         .collect();
 
     let mut writer = csv::Writer::from_writer(io::stdout());
 
-    writer.write_record(&["epochs", "train_accuracy", "test_accuracy"])?;
+    writer.write_record(["epochs", "train_accuracy", "test_accuracy", "loss", "test_loss"])?;
     writer.flush()?;
 
     let train_result = test(&network, &train_test_data);
     let test_result = test(&network, &test_data);
 
-    writer.write_record(&["0", format!("{}", train_result).as_str(), format!("{}", test_result).as_str()])?;
+    let loss = network.evaluate_loss(&training_data);
+    let test_loss = network.evaluate_loss(&test_loss_data);
+
+    writer.write_record(["0", format!("{}", train_result).as_str(), format!("{}", test_result).as_str(), format!("{}", loss).as_str(), format!("{}", test_loss).as_str()])?;
     writer.flush()?;
 
-    for epoch in 1..=30 {
-        network.stochastic_gradient_descent(training_data.clone(), BATCH_SIZE, LEARNING_RATE);
+    let mut trainer = Trainer::new(TrainingOptions::new(LEARNING_RATE), BATCH_SIZE, 30);
+    trainer.restore_best = false;
 
-        let train_result = test(&network, &train_test_data);
-        let test_result = test(&network, &test_data);
+    trainer.add_callback(CsvLogger {
+        writer,
+        train_test_data,
+        test_data,
+    });
 
-        writer.write_record(&[format!("{}", epoch), format!("{}", train_result), format!("{}", test_result)])?;
-        writer.flush()?;
-    }
+    trainer.train(&mut network, &training_data, Some(&test_loss_data));
 
     Ok(())
 }
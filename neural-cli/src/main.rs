@@ -1,12 +1,27 @@
-use bincode;
+#![allow(clippy::needless_return, clippy::too_many_arguments)]
+
+use byteorder::{BigEndian, WriteBytesExt};
 use clap::{ArgAction, Parser, Subcommand};
-use neural::layer::PoolType;
-use neural::{layer, ActivationFunction, CostFunction, Float, Network, Layer};
-use neural_utils::{io, outputs_from_labels};
+use neural::layer::{PoolType, UpsampleType};
+use neural::metadata::TrainingRun;
+use neural::trainer::{self, BatchResult, Callback, EarlyStopping, EpochResult, Metric};
+use neural::{
+    layer, ActivationFunction, ClassificationReport, CostFunction, Float, GradientClipping,
+    Initializer, Layer, LearningRateScheduler, Metadata, Network, NetworkBuilder, Normalization,
+    Preprocessing, RegressionReport, Regularization, Trainer, TrainingOptions,
+};
+use neural_utils::augmentation::{Augmentation, Augmenter};
+use neural_utils::csv::{self, CsvOptions};
+use neural_utils::npy;
+use neural_utils::preset::Preset;
+use neural_utils::{idx, idx_preprocessing, inputs_from_bytes, io, outputs_from_labels};
 use rand::seq::SliceRandom;
+use std::io::{IsTerminal, Write};
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str::FromStr;
 
 #[cfg(feature = "high-precision")]
 static FILE_EXTENSION: &str = "nn64";
@@ -14,6 +29,15 @@ static FILE_EXTENSION: &str = "nn64";
 #[cfg(not(feature = "high-precision"))]
 static FILE_EXTENSION: &str = "nn32";
 
+static PRESETS: &[&str] = &[
+    "mnist",
+    "fashion-mnist",
+    "emnist-letters",
+    "emnist-digits",
+    "emnist-balanced",
+    "emnist-byclass",
+];
+
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
@@ -25,6 +49,7 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Create a new neural network
     Create {
@@ -33,20 +58,34 @@ enum Commands {
 
         /// A layer that should be added to the network
         ///
-        /// Specified with [activation_function]:[size].
+        /// Specified with [layer_type]:[parameters], e.g. fc:[activation_function]:[size].
+        /// Fully connected layers accept init=[initializer] and bias-init=[initializer] options,
+        /// e.g. fc:relu:128:init=he-normal or fc:tanh:10:init=file(weights.txt).
+        /// 1-dimensional layers are conv1d:[activation_function]:[input_channels]:[output_channels]:[kernel_size]
+        /// with stride=, dilation=, padding= and init options, and pool1d:[pool_type]:[channels]:[kernel_size] with a stride= option.
+        /// Image layers infer their input from the previous layer shape, set with reshape:[channels]:[height]:[width],
+        /// e.g. reshape:1:28:28 followed by pool:max:2, upsample:[upsample_type]:[scale] or globalpool:[pool_type].
+        /// flatten turns the previous layer into a flat vector again.
         /// Must start with input:[size]
         #[clap(
             short,
             long = "layer",
             action(ArgAction::Append),
             min_values(2),
-            required(true)
+            required_unless_present("config")
         )]
         layers: Vec<String>,
 
         /// The cost function
-        #[clap(short, long)]
-        cost_function: String,
+        #[clap(short, long, required_unless_present("config"))]
+        cost_function: Option<String>,
+
+        /// A TOML or JSON model spec to create the network from, instead of layers and a cost function
+        #[clap(long, conflicts_with_all(&["layers", "cost-function"]))]
+        config: Option<PathBuf>,
+
+        #[clap(flatten)]
+        metadata: MetadataArgs,
     },
 
     /// Train a neural network with the provided data set
@@ -54,23 +93,32 @@ enum Commands {
         /// The neural network file
         network: PathBuf,
 
-        /// The input IDX dataset file
+        /// The input IDX, CSV or NumPy dataset file, where arrays of .npz archives are selected with e.g. data.npz:inputs
         inputs: PathBuf,
 
-        /// The label IDX dataset file
-        labels: PathBuf,
+        /// The label IDX or NumPy dataset file, or the targets for regression. Not needed for CSV inputs with --targets
+        labels: Option<PathBuf>,
 
         /// The learning rate
         #[clap(short = 'r', long)]
         learning_rate: Float,
 
+        /// The learning rate schedule, applied to the learning rate per epoch
+        ///
+        /// One of constant, step:[step]:[factor], exponential:[gamma],
+        /// cosine:[period][:multiplier][:min] (annealing with warm restarts), one-cycle:[epochs][:warmup],
+        /// or plateau:[factor]:[patience][:min], which reduces the learning rate when the validation metric stops improving.
+        /// Prefix with warmup:[epochs]: for a linear warmup, e.g. warmup:5:cosine:10.
+        #[clap(long, default_value = "constant")]
+        lr_schedule: String,
+
         /// The thread count
         #[clap(short = 'p', long, default_value = "1")]
         thread_count: usize,
 
         /// The batch size
         #[clap(short = 's', long, default_value = "10")]
-        batch_size: usize,
+        batch_size: NonZeroUsize,
 
         /// The number of batches to train, defaulting to all batches
         #[clap(short = 'c', long)]
@@ -81,12 +129,40 @@ enum Commands {
         epochs: usize,
 
         /// If provided, tests the network with these inputs at every epoch
-        #[clap(long, requires("test-labels"))]
+        #[clap(long)]
         test_inputs: Option<PathBuf>,
 
         /// If provided, tests the network with these labels at every epoch
         #[clap(long, requires("test-inputs"))]
         test_labels: Option<PathBuf>,
+
+        /// Whether the labels are class indices, or regression targets read as floats from an IDX or CSV file
+        #[clap(long, default_value = "classification", possible_values(&["classification", "regression"]))]
+        task: String,
+
+        /// A dataset preset, which reads EMNIST images upright, converts the labels to class indices and checks the network shape
+        #[clap(long, possible_values(PRESETS), conflicts_with("task"))]
+        preset: Option<String>,
+
+        /// Fits preprocessing on the training inputs, which is stored with the network and applied at inference time
+        ///
+        /// Comma separated steps of standardize (per feature), mean (subtracts the mean input), scale:[factor]
+        /// and clip:[min]:[max], e.g. standardize,clip:-3:3. Replaces the existing preprocessing of the network.
+        /// The bytes of IDX inputs are always scaled to [0, 1] first.
+        #[clap(long, value_name = "STEPS")]
+        normalize: Option<String>,
+
+        #[clap(flatten)]
+        augmentation: AugmentationArgs,
+
+        #[clap(flatten)]
+        csv: CsvArgs,
+
+        #[clap(flatten)]
+        validation: ValidationArgs,
+
+        #[clap(flatten)]
+        regularization: RegularizationArgs,
     },
 
     /// Test a neural network with the provided data set
@@ -94,15 +170,29 @@ enum Commands {
         /// The neural network file
         network: PathBuf,
 
-        /// The input IDX dataset file
+        /// The input IDX, CSV or NumPy dataset file, where arrays of .npz archives are selected with e.g. data.npz:inputs
         inputs: PathBuf,
 
-        /// The label IDX dataset file
-        labels: PathBuf,
+        /// The label IDX or NumPy dataset file, or the targets for regression. Not needed for CSV inputs with --targets
+        labels: Option<PathBuf>,
 
         /// The amount of samples to test
         #[clap(short, long)]
         count: Option<usize>,
+
+        /// Whether the labels are class indices, or regression targets read as floats from an IDX or CSV file
+        #[clap(long, default_value = "classification", possible_values(&["classification", "regression"]))]
+        task: String,
+
+        /// A dataset preset, which reads EMNIST images upright, converts the labels to class indices and checks the network shape
+        #[clap(long, possible_values(PRESETS), conflicts_with("task"))]
+        preset: Option<String>,
+
+        #[clap(flatten)]
+        csv: CsvArgs,
+
+        #[clap(flatten)]
+        report: ReportArgs,
     },
 
     /// Evaluate a neural network with the provided input
@@ -117,6 +207,381 @@ enum Commands {
         #[clap(short, long, value_name = "FILE")]
         output: Option<PathBuf>,
     },
+
+    /// Predict the outputs of a neural network for a whole IDX, CSV or NumPy input dataset
+    Predict {
+        /// The neural network file
+        network: PathBuf,
+
+        /// The input IDX, CSV or NumPy dataset file
+        inputs: PathBuf,
+
+        /// The output file
+        output: PathBuf,
+
+        /// What to write for every sample: the largest output, the k largest outputs or all outputs
+        #[clap(short, long, default_value = "outputs", possible_values(&["argmax", "top-k", "outputs"]))]
+        mode: String,
+
+        /// The amount of classes to write in top-k mode
+        #[clap(short = 'k', long, default_value = "5")]
+        top_k: usize,
+
+        /// The output format, defaulting to the extension of the output file.
+        /// IDX files contain f32 values and NumPy arrays contain floats, with the classes and outputs as pairs for top-k.
+        #[clap(short, long, possible_values(&["csv", "jsonl", "idx", "npy"]))]
+        format: Option<String>,
+
+        /// The amount of samples that are predicted and written at once
+        #[clap(short = 's', long, default_value = "256")]
+        batch_size: usize,
+
+        /// The thread count
+        #[clap(short = 'p', long, default_value = "1")]
+        thread_count: usize,
+
+        /// A dataset preset, which reads EMNIST images upright
+        #[clap(long, possible_values(PRESETS))]
+        preset: Option<String>,
+
+        #[clap(flatten)]
+        csv: CsvArgs,
+    },
+
+    /// Export the weights and biases of the fully connected layers of a neural network to a NumPy .npz archive
+    ///
+    /// The arrays are named layers.[index].weights, with the shape (layer size, previous layer size), and layers.[index].biases.
+    Export {
+        /// The neural network file
+        network: PathBuf,
+
+        /// The .npz output file
+        output: PathBuf,
+    },
+
+    /// Import the weights and biases of fully connected layers from a NumPy .npz archive, named like the export
+    Import {
+        /// The neural network file, which is overwritten
+        network: PathBuf,
+
+        /// The .npz input file
+        weights: PathBuf,
+    },
+
+    /// Show the layers, shapes and weight statistics of a neural network
+    Info {
+        /// The neural network file
+        network: PathBuf,
+
+        /// The output format
+        #[clap(short, long, default_value = "table", possible_values(&["table", "json"]))]
+        format: String,
+
+        /// An input IDX dataset file, of which a sample is used to find dead ReLU units
+        #[clap(long)]
+        sample: Option<PathBuf>,
+
+        /// The amount of sample inputs to use
+        #[clap(long, default_value = "100")]
+        sample_count: usize,
+    },
+}
+
+/// Metadata stored with a new network, overriding the metadata of a model spec
+#[derive(clap::Args)]
+struct MetadataArgs {
+    /// A description of the network
+    #[clap(long)]
+    description: Option<String>,
+
+    /// Comma separated names of the outputs, e.g. cat,dog
+    #[clap(long, value_name = "NAMES")]
+    class_names: Option<String>,
+
+    /// The width and height of image inputs, e.g. 28x28
+    #[clap(long, value_name = "WIDTHxHEIGHT")]
+    image_size: Option<String>,
+
+    /// A dataset preset, which sets the class names and image size and checks the network shape
+    #[clap(long, possible_values(PRESETS))]
+    preset: Option<String>,
+}
+
+impl MetadataArgs {
+    fn apply(&self, metadata: &mut Metadata) -> Result<(), String> {
+        if let Some(description) = &self.description {
+            metadata.description = Some(description.clone());
+        }
+
+        if let Some(class_names) = &self.class_names {
+            metadata.class_names = class_names.split(',').map(|s| s.to_string()).collect();
+        }
+
+        if let Some(image_size) = &self.image_size {
+            let dimensions = image_size
+                .split_once('x')
+                .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+
+            match dimensions {
+                Some(dimensions) => metadata.image_dimensions = Some(dimensions),
+                None => return Err(format!("Invalid image size: {}", image_size)),
+            }
+        }
+
+        if let Some(preset) = parse_preset(&self.preset) {
+            preset.fill_metadata(metadata);
+        }
+
+        return Ok(());
+    }
+}
+
+/// Classification report options for testing
+#[derive(clap::Args)]
+struct ReportArgs {
+    /// Prints a classification report with a confusion matrix, per class precision, recall and F1, top-k accuracy and log loss
+    #[clap(long)]
+    report: bool,
+
+    /// The format of the classification report
+    #[clap(long, default_value = "table", possible_values(&["table", "csv", "json"]))]
+    report_format: String,
+
+    /// Writes the classification report to a file, instead of printing it
+    #[clap(long, value_name = "FILE")]
+    report_output: Option<PathBuf>,
+
+    /// The k of the top-k accuracy, can be given multiple times
+    #[clap(long, action(ArgAction::Append), default_value = "5")]
+    top_k: Vec<usize>,
+
+    /// Comma separated names of the classes in the report, defaulting to the class names of the network
+    #[clap(long, value_name = "NAMES")]
+    class_names: Option<String>,
+}
+
+/// Validation, early stopping and best network selection options for training
+#[derive(clap::Args)]
+struct ValidationArgs {
+    /// Splits this fraction off the training data to validate the network at every epoch, instead of test inputs
+    #[clap(long, conflicts_with("test-inputs"), value_parser = parse_fraction)]
+    validation_split: Option<Float>,
+
+    /// The validation metric used to select the best network and stop early, defaulting to accuracy, or loss for regression
+    #[clap(long, possible_values(&["accuracy", "loss"]))]
+    metric: Option<String>,
+
+    /// Stops training when the validation metric hasn't improved for this many epochs
+    #[clap(long, value_name = "PATIENCE", value_parser = parse_positive)]
+    early_stopping: Option<usize>,
+
+    /// The minimum change of the validation metric that counts as an improvement
+    #[clap(long, default_value = "0")]
+    min_delta: Float,
+
+    /// Saves the network of the last epoch, instead of the network with the best validation metric
+    #[clap(long)]
+    keep_last: bool,
+}
+
+fn parse_fraction(value: &str) -> Result<Float, String> {
+    match value.parse::<Float>() {
+        Ok(fraction) if fraction > 0.0 && fraction < 1.0 => Ok(fraction),
+        _ => Err("The fraction should be between 0 and 1".to_string()),
+    }
+}
+
+fn parse_positive(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(value) if value > 0 => Ok(value),
+        _ => Err("The value should be a positive integer".to_string()),
+    }
+}
+
+/// Options for CSV datasets, where the features and targets are columns of the same file
+#[derive(clap::Args)]
+struct CsvArgs {
+    /// Comma separated feature columns of CSV inputs, by name or index, defaulting to all other columns
+    #[clap(long, value_name = "COLUMNS")]
+    features: Option<String>,
+
+    /// Comma separated columns of CSV inputs that aren't features, e.g. identifiers
+    #[clap(long, value_name = "COLUMNS")]
+    ignore: Option<String>,
+
+    /// Comma separated target columns of CSV inputs, by name or index, which replace the labels file.
+    /// For classification, a single column of class names, which are mapped to the class names of the network.
+    /// Networks without class names use the sorted distinct values.
+    #[clap(long, value_name = "COLUMNS")]
+    targets: Option<String>,
+
+    /// Whether CSV files start with a header row, detected by default
+    #[clap(long, default_value = "auto", possible_values(&["auto", "yes", "no"]))]
+    header: String,
+
+    /// The delimiter of CSV files
+    #[clap(long, default_value = ",", value_parser = parse_delimiter)]
+    delimiter: u8,
+
+    /// What to do with missing values (empty, NA, NaN or ?) in CSV files:
+    /// fail, skip the row, or replace missing features with 0 or the column mean
+    #[clap(long, default_value = "error", possible_values(&["error", "skip", "zero", "mean"]))]
+    missing: String,
+}
+
+impl CsvArgs {
+    fn options(&self, categorical: bool, classes: &[String]) -> CsvOptions {
+        let columns = |columns: &str| columns.split(',').filter_map(|s| s.parse().ok()).collect();
+
+        return CsvOptions {
+            header: match self.header.as_str() {
+                "yes" => Some(true),
+                "no" => Some(false),
+                _ => None,
+            },
+            delimiter: self.delimiter,
+            features: self.features.as_deref().map(columns),
+            targets: self.targets.as_deref().map(columns).unwrap_or_default(),
+            ignored: self.ignore.as_deref().map(columns).unwrap_or_default(),
+            categorical,
+            classes: classes.to_vec(),
+            missing: self.missing.parse().expect("Invalid missing value policy"),
+        };
+    }
+
+    /// The options to only read the features, where the target columns are ignored
+    fn input_options(&self) -> CsvOptions {
+        let mut options = self.options(false, &[]);
+
+        options.ignored.append(&mut options.targets);
+
+        return options;
+    }
+
+    /// Whether the targets of the inputs can be read without a labels file
+    fn has_targets(&self, inputs_path: &Path) -> bool {
+        return is_csv(inputs_path) && self.targets.is_some();
+    }
+}
+
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        [delimiter] => Ok(*delimiter),
+        _ => Err("The delimiter should be a single ASCII character".to_string()),
+    }
+}
+
+/// Random image augmentation of the training inputs
+#[derive(clap::Args)]
+struct AugmentationArgs {
+    /// Randomly augments every training image at every epoch, before preprocessing other than scaling IDX bytes to [0, 1]
+    ///
+    /// Comma separated augmentations of shift:[pixels], rotate:[degrees], scale:[amount] (scales by up to 1 ± amount, with amount in [0, 1)),
+    /// elastic:[alpha]:[sigma], noise:[std] and erase:[probability], e.g. shift:2,rotate:10,erase:0.5.
+    /// Images are square unless the network has image dimensions. Noise is relative to IDX images scaled to [0, 1].
+    #[clap(long, value_name = "AUGMENTATIONS")]
+    augment: Option<String>,
+
+    /// The seed of the augmentation, which makes it deterministic
+    #[clap(long, requires("augment"))]
+    seed: Option<u64>,
+}
+
+impl AugmentationArgs {
+    fn augmenter(&self, network: &Network) -> Result<Option<Augmenter>, String> {
+        let Some(augment) = &self.augment else {
+            return Ok(None);
+        };
+
+        let augmentations = augment
+            .split(',')
+            .map(|s| s.parse())
+            .collect::<Result<Vec<Augmentation>, _>>()
+            .map_err(|_| format!("Invalid augmentation: {}", augment))?;
+
+        let input_size = network.shape()[0];
+        let (width, height) = match network.metadata.image_dimensions {
+            Some(dimensions) => dimensions,
+            None => {
+                let side = (input_size as f64).sqrt().round() as usize;
+
+                (side, side)
+            }
+        };
+
+        if width * height != input_size {
+            return Err(format!(
+                "Can't augment {} inputs, set the image dimensions of the network with create --image-size",
+                input_size
+            ));
+        }
+
+        return Ok(Some(Augmenter::new(
+            augmentations,
+            width,
+            height,
+            self.seed,
+        )));
+    }
+}
+
+/// Weight regularization and gradient clipping options for training
+#[derive(clap::Args)]
+struct RegularizationArgs {
+    /// The L1 weight penalty for all layers
+    #[clap(long, default_value = "0")]
+    l1: Float,
+
+    /// The L2 weight penalty (weight decay) for all layers
+    #[clap(long, default_value = "0")]
+    l2: Float,
+
+    /// The L1 and L2 weight penalties for a single layer, specified with [layer index]:[l1]:[l2]
+    #[clap(long, action(ArgAction::Append), value_name = "LAYER:L1:L2")]
+    layer_regularization: Vec<String>,
+
+    /// Clamps every gradient value to [-value, value]
+    #[clap(long, conflicts_with("clip-norm"))]
+    clip_value: Option<Float>,
+
+    /// Scales the gradients down when their combined L2 norm over all layers exceeds this value
+    #[clap(long)]
+    clip_norm: Option<Float>,
+}
+
+impl RegularizationArgs {
+    /// Creates training options with the given learning rate.
+    fn training_options(&self, learning_rate: Float) -> Result<TrainingOptions, String> {
+        let mut options = TrainingOptions::new(learning_rate);
+
+        options.regularization = Regularization::new(self.l1, self.l2);
+
+        for layer in &self.layer_regularization {
+            let parts: Vec<&str> = layer.split(':').collect();
+
+            let (index, l1, l2) = match parts.as_slice() {
+                [index, l1, l2] => (index.parse(), l1.parse(), l2.parse()),
+                _ => return Err(format!("Invalid layer regularization: {}", layer)),
+            };
+
+            match (index, l1, l2) {
+                (Ok(index), Ok(l1), Ok(l2)) => {
+                    options
+                        .layer_regularization
+                        .insert(index, Regularization::new(l1, l2));
+                }
+                _ => return Err(format!("Invalid layer regularization: {}", layer)),
+            }
+        }
+
+        options.clipping = match (self.clip_value, self.clip_norm) {
+            (Some(value), _) => Some(GradientClipping::Value(value)),
+            (_, Some(norm)) => Some(GradientClipping::Norm(norm)),
+            _ => None,
+        };
+
+        return Ok(options);
+    }
 }
 
 fn main() {
@@ -132,51 +597,170 @@ fn main() {
             network,
             layers,
             cost_function,
-        } => create(network, layers, cost_function),
+            config,
+            metadata,
+        } => create(network, layers, cost_function, config, metadata),
         Commands::Train {
             network,
             inputs,
             labels,
             learning_rate,
+            lr_schedule,
             thread_count,
             batch_size,
             batch_count,
             epochs,
             test_inputs,
             test_labels,
+            task,
+            preset,
+            normalize,
+            augmentation,
+            csv,
+            validation,
+            regularization,
         } => train(
             network,
             inputs,
             labels,
             learning_rate,
+            lr_schedule,
             thread_count,
             batch_size,
             batch_count,
             epochs,
             test_inputs,
             test_labels,
-            cli.verbose,
+            task == "regression",
+            parse_preset(preset),
+            normalize,
+            augmentation,
+            csv,
+            validation,
+            regularization,
         ),
         Commands::Test {
             network,
             inputs,
             labels,
             count,
-        } => test(network, inputs, labels, count, cli.verbose),
+            task,
+            preset,
+            csv,
+            report,
+        } => test(
+            network,
+            inputs,
+            labels,
+            count,
+            task == "regression",
+            parse_preset(preset),
+            csv,
+            report,
+            cli.verbose,
+        ),
         Commands::Evaluate {
             network,
             input,
             output,
         } => evaluate(network, input, output),
+        Commands::Predict {
+            network,
+            inputs,
+            output,
+            mode,
+            top_k,
+            format,
+            batch_size,
+            thread_count,
+            preset,
+            csv,
+        } => predict(
+            network,
+            inputs,
+            output,
+            mode,
+            *top_k,
+            format,
+            *batch_size,
+            *thread_count,
+            parse_preset(preset),
+            csv,
+        ),
+        Commands::Export { network, output } => export(network, output),
+        Commands::Import { network, weights } => import(network, weights),
+        Commands::Info {
+            network,
+            format,
+            sample,
+            sample_count,
+        } => info(network, format, sample, sample_count),
+    }
+}
+
+fn create(
+    network_path: &PathBuf,
+    layers: &[String],
+    cost_function: &Option<String>,
+    config: &Option<PathBuf>,
+    metadata: &MetadataArgs,
+) {
+    let mut network = match config {
+        Some(config) => match io::read_model_spec(config) {
+            Ok(spec) => match NetworkBuilder::new(spec).build() {
+                Ok(network) => network,
+                Err(error) => {
+                    println!("Invalid model spec: {}", error);
+                    return;
+                }
+            },
+            Err(error) => {
+                println!("Couldn't read {}: {}", config.display(), error);
+                return;
+            }
+        },
+        None => match network_from_layers(layers, cost_function.as_deref().unwrap_or_default()) {
+            Some(network) => network,
+            None => return,
+        },
+    };
+
+    if let Err(error) = metadata.apply(&mut network.metadata) {
+        println!("{}", error);
+        return;
+    }
+
+    if let Some(Err(error)) =
+        parse_preset(&metadata.preset).map(|preset| preset.check_shape(&network.shape()))
+    {
+        println!("{}", error);
+        return;
     }
+
+    let encoded = match network.encode() {
+        Ok(encoded) => encoded,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    match io::write_file(&encoded, network_path, true, Some(FILE_EXTENSION)) {
+        Err(error) => {
+            println!("Couldn't write to {}: {}", network_path.display(), error);
+            return;
+        }
+        Ok(_) => println!("Created a new neural network at {}", network_path.display()),
+    };
 }
 
-fn create(network_path: &PathBuf, layers: &[String], cost_function: &String) {
+/// Creates a network from layer specifications, printing an error and returning None if they are invalid.
+fn network_from_layers(layers: &[String], cost_function: &str) -> Option<Network> {
     let cost_function: CostFunction = match cost_function.parse() {
         Ok(cost_function) => cost_function,
         Err(_) => {
             println!("Invalid cost function: {}", cost_function);
-            return;
+            return None;
         }
     };
 
@@ -185,6 +769,7 @@ fn create(network_path: &PathBuf, layers: &[String], cost_function: &String) {
     for layer in layers {
         let mut split = layer.split(":");
         let layer_type = split.next().expect("Missing layer type");
+        let result;
 
         if layer_type == "input" {
             let size = split.next().expect("Missing layer size");
@@ -193,11 +778,11 @@ fn create(network_path: &PathBuf, layers: &[String], cost_function: &String) {
                 Ok(size) => usize::from(size),
                 Err(_) => {
                     println!("Invalid layer size: {}", size);
-                    return;
+                    return None;
                 }
             };
 
-            network.add_layer(layer::Input::new(size));
+            result = network.add_layer(layer::Input::new(size));
         } else if layer_type == "fc" || layer_type == "fully-connected" {
             let activation_function = split.next().expect("Missing activation function");
             let size = split.next().expect("Missing layer size");
@@ -206,7 +791,7 @@ fn create(network_path: &PathBuf, layers: &[String], cost_function: &String) {
                 Ok(activation_function) => activation_function,
                 Err(_) => {
                     println!("Invalid activation function: {}", activation_function);
-                    return;
+                    return None;
                 }
             };
 
@@ -214,271 +799,1407 @@ fn create(network_path: &PathBuf, layers: &[String], cost_function: &String) {
                 Ok(size) => usize::from(size),
                 Err(_) => {
                     println!("Invalid layer size: {}", size);
-                    return;
+                    return None;
+                }
+            };
+
+            let options = match layer_options(split, &["init", "bias-init"]) {
+                Ok(options) => options,
+                Err(error) => {
+                    println!("{}", error);
+                    return None;
+                }
+            };
+
+            let (weight_initializer, bias_initializer) = match (
+                layer_initializer(&options, "init"),
+                layer_initializer(&options, "bias-init"),
+            ) {
+                (Ok(weight_initializer), Ok(bias_initializer)) => {
+                    (weight_initializer, bias_initializer)
+                }
+                (Err(error), _) | (_, Err(error)) => {
+                    println!("{}", error);
+                    return None;
                 }
             };
 
             if let Some(last_layer) = network.layers.last() {
-                network.add_layer(layer::FullyConnected::new(
+                result = layer::FullyConnected::with_initializers(
                     last_layer.size(),
                     size,
                     activation_function,
-                ));
+                    weight_initializer,
+                    bias_initializer,
+                )
+                .and_then(|layer| network.add_layer(layer));
             } else {
                 println!("No input layer");
-                return;
+                return None;
             }
-        } else if layer_type == "pool" || layer_type == "pool2d" {
-            let pool_type = split.next().expect("Missing pool type");
+        } else if layer_type == "conv1d" {
+            let activation_function = split.next().expect("Missing activation function");
+            let sizes: Vec<_> = split.by_ref().take(3).collect();
 
-            let pool_type: PoolType = match pool_type.parse() {
-                Ok(pool_type) => pool_type,
+            let activation_function: ActivationFunction = match activation_function.parse() {
+                Ok(activation_function) => activation_function,
                 Err(_) => {
-                    println!("Invalid pool type: {}", pool_type);
-                    return;
+                    println!("Invalid activation function: {}", activation_function);
+                    return None;
                 }
             };
 
-            let pool_params: Vec<_> = split
-                .take(4)
-                .map(|x| match x.parse::<NonZeroUsize>() {
-                    Ok(x) => usize::from(x),
-                    Err(_) => {
-                        println!("Invalid pool size parameter: {}", x);
-                        return 0;
-                    }
-                })
-                .collect();
+            let sizes = match sizes
+                .iter()
+                .map(|x| x.parse::<NonZeroUsize>().map(usize::from))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(sizes) if sizes.len() == 3 => sizes,
+                _ => {
+                    println!("Invalid conv1d parameters. Should be conv1d:[activation_function]:[input_channels]:[output_channels]:[kernel_size]");
+                    return None;
+                }
+            };
 
-            let input_width;
-            let input_height;
-            let kernel_width;
-            let kernel_height;
+            let (input_channels, output_channels, kernel_size) = (sizes[0], sizes[1], sizes[2]);
 
-            if pool_params.contains(&0) {
-                return;
-            }
+            let options = match layer_options(
+                split,
+                &["stride", "dilation", "padding", "init", "bias-init"],
+            ) {
+                Ok(options) => options,
+                Err(error) => {
+                    println!("{}", error);
+                    return None;
+                }
+            };
 
-            if pool_params.len() == 2 {
-                input_width = pool_params[0];
-                input_height = pool_params[0];
-                kernel_width = pool_params[1];
-                kernel_height = pool_params[1];
-            } else if pool_params.len() == 4 {
-                input_width = pool_params[0];
-                input_height = pool_params[1];
-                kernel_width = pool_params[2];
-                kernel_height = pool_params[3];
-            } else {
-                println!("Invalid pool parameter length. Should be 2 for square input and kernel or 4 for rectangular input and kernel");
-                return;
-            }
+            let convolution_options = match (
+                layer_option(&options, "stride", 1),
+                layer_option(&options, "dilation", 1),
+                layer_option(&options, "padding", 0),
+            ) {
+                (Ok(stride), Ok(dilation), Ok(padding)) if stride > 0 && dilation > 0 => {
+                    layer::ConvolutionOptions {
+                        stride,
+                        dilation,
+                        padding,
+                    }
+                }
+                _ => {
+                    println!("Invalid conv1d stride, dilation or padding");
+                    return None;
+                }
+            };
+
+            let (weight_initializer, bias_initializer) = match (
+                layer_initializer(&options, "init"),
+                layer_initializer(&options, "bias-init"),
+            ) {
+                (Ok(weight_initializer), Ok(bias_initializer)) => {
+                    (weight_initializer, bias_initializer)
+                }
+                (Err(error), _) | (_, Err(error)) => {
+                    println!("{}", error);
+                    return None;
+                }
+            };
 
             if let Some(last_layer) = network.layers.last() {
-                if last_layer.size() != input_width * input_height {
+                if last_layer.size() % input_channels != 0 {
                     println!(
-                        "Invalid input size {} * {} = {}. Previous layer is of size {}.",
-                        input_width,
-                        input_height,
-                        input_width * input_height,
-                        last_layer.size()
+                        "Previous layer of size {} can't be split into {} channels.",
+                        last_layer.size(),
+                        input_channels
                     );
-                    return;
+                    return None;
                 }
 
-                network.add_layer(layer::Pool2D::new(
-                    pool_type,
-                    input_width,
-                    input_height,
-                    kernel_width,
-                    kernel_height,
-                ));
+                let input_length = last_layer.size() / input_channels;
+
+                result = layer::Conv1D::with_initializers(
+                    input_channels,
+                    input_length,
+                    output_channels,
+                    kernel_size,
+                    convolution_options,
+                    activation_function,
+                    weight_initializer,
+                    bias_initializer,
+                )
+                .and_then(|layer| network.add_layer(layer));
             } else {
                 println!("No input layer");
-                return;
+                return None;
             }
-        } else {
-            println!("Unknown layer type: {}", layer_type);
-            return;
-        }
-    }
+        } else if layer_type == "pool1d" {
+            let pool_type = split.next().expect("Missing pool type");
+            let sizes: Vec<_> = split.by_ref().take(2).collect();
 
-    let encoded: Vec<u8> = bincode::encode_to_vec(network, bincode::config::standard()).expect("Unable to encode network");
+            let pool_type: PoolType = match pool_type.parse() {
+                Ok(pool_type) => pool_type,
+                Err(_) => {
+                    println!("Invalid pool type: {}", pool_type);
+                    return None;
+                }
+            };
 
-    match io::write_file(&encoded, network_path, true, Some(FILE_EXTENSION)) {
-        Err(error) => {
-            println!("Couldn't write to {}: {}", network_path.display(), error);
-            return;
+            let sizes = match sizes
+                .iter()
+                .map(|x| x.parse::<NonZeroUsize>().map(usize::from))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(sizes) if sizes.len() == 2 => sizes,
+                _ => {
+                    println!("Invalid pool1d parameters. Should be pool1d:[pool_type]:[channels]:[kernel_size]");
+                    return None;
+                }
+            };
+
+            let (channels, kernel_size) = (sizes[0], sizes[1]);
+
+            let stride = match layer_options(split, &["stride"])
+                .and_then(|options| layer_option(&options, "stride", kernel_size))
+            {
+                Ok(stride) if stride > 0 => stride,
+                Ok(_) => {
+                    println!("Invalid pool1d stride");
+                    return None;
+                }
+                Err(error) => {
+                    println!("{}", error);
+                    return None;
+                }
+            };
+
+            if let Some(last_layer) = network.layers.last() {
+                if last_layer.size() % channels != 0 {
+                    println!(
+                        "Previous layer of size {} can't be split into {} channels.",
+                        last_layer.size(),
+                        channels
+                    );
+                    return None;
+                }
+
+                result = layer::Pool1D::new(
+                    pool_type,
+                    channels,
+                    last_layer.size() / channels,
+                    kernel_size,
+                    stride,
+                )
+                .and_then(|layer| network.add_layer(layer));
+            } else {
+                println!("No input layer");
+                return None;
+            }
+        } else if layer_type == "pool" || layer_type == "pool2d" {
+            let pool_type = split.next().expect("Missing pool type");
+
+            let pool_type: PoolType = match pool_type.parse() {
+                Ok(pool_type) => pool_type,
+                Err(_) => {
+                    println!("Invalid pool type: {}", pool_type);
+                    return None;
+                }
+            };
+
+            let pool_params: Vec<_> = split
+                .take(4)
+                .map(|x| match x.parse::<NonZeroUsize>() {
+                    Ok(x) => usize::from(x),
+                    Err(_) => {
+                        println!("Invalid pool size parameter: {}", x);
+                        return 0;
+                    }
+                })
+                .collect();
+
+            if pool_params.contains(&0) {
+                return None;
+            }
+
+            let pool = match pool_params.as_slice() {
+                [kernel_size] => Ok(layer::Pool2D::inferred(
+                    pool_type,
+                    *kernel_size,
+                    *kernel_size,
+                )),
+                [input_size, kernel_size] => {
+                    layer::Pool2D::new_square(pool_type, *input_size, *kernel_size)
+                }
+                [input_width, input_height, kernel_width, kernel_height] => layer::Pool2D::new(
+                    pool_type,
+                    *input_width,
+                    *input_height,
+                    *kernel_width,
+                    *kernel_height,
+                ),
+                _ => {
+                    println!("Invalid pool parameter length. Should be 1 for an inferred input and square kernel, 2 for square input and kernel or 4 for rectangular input and kernel");
+                    return None;
+                }
+            };
+
+            result = pool.and_then(|pool| network.add_layer(pool));
+        } else if layer_type == "reshape" {
+            let dimensions: Vec<_> = split.map(|x| x.parse::<NonZeroUsize>()).collect();
+
+            let shape = match dimensions.as_slice() {
+                [Ok(channels), Ok(height), Ok(width)] => layer::Shape::new(
+                    usize::from(*channels),
+                    usize::from(*height),
+                    usize::from(*width),
+                ),
+                [Ok(height), Ok(width)] => {
+                    layer::Shape::new(1, usize::from(*height), usize::from(*width))
+                }
+                _ => {
+                    println!("Invalid reshape parameters. Should be reshape:[channels]:[height]:[width] or reshape:[height]:[width]");
+                    return None;
+                }
+            };
+
+            result = network.add_layer(layer::Reshape::new(shape));
+        } else if layer_type == "flatten" {
+            result = network.add_layer(layer::Flatten::new());
+        } else if layer_type == "globalpool" || layer_type == "global-pool" {
+            let pool_type = split.next().expect("Missing pool type");
+
+            let pool_type: PoolType = match pool_type.parse() {
+                Ok(pool_type) => pool_type,
+                Err(_) => {
+                    println!("Invalid pool type: {}", pool_type);
+                    return None;
+                }
+            };
+
+            if let Some(last_layer) = network.layers.last() {
+                result = network.add_layer(layer::GlobalPool::new(pool_type, last_layer.shape()));
+            } else {
+                println!("No input layer");
+                return None;
+            }
+        } else if layer_type == "upsample" || layer_type == "upsample2d" {
+            let upsample_type = split.next().expect("Missing upsample type");
+            let scale = split.next().expect("Missing upsample scale");
+
+            let upsample_type: UpsampleType = match upsample_type.parse() {
+                Ok(upsample_type) => upsample_type,
+                Err(_) => {
+                    println!("Invalid upsample type: {}", upsample_type);
+                    return None;
+                }
+            };
+
+            let scale = match scale.parse::<NonZeroUsize>() {
+                Ok(scale) => usize::from(scale),
+                Err(_) => {
+                    println!("Invalid upsample scale: {}", scale);
+                    return None;
+                }
+            };
+
+            result = network.add_layer(layer::Upsample2D::inferred(upsample_type, scale, scale));
+        } else {
+            println!("Unknown layer type: {}", layer_type);
+            return None;
+        }
+
+        if let Err(error) = result {
+            println!("Invalid layer {}: {}", layer, error);
+            return None;
+        }
+    }
+
+    return Some(network);
+}
+
+/// Parses the remaining layer specification parts as [key]=[value] options.
+fn layer_options<'a>(
+    options: impl Iterator<Item = &'a str>,
+    allowed: &[&str],
+) -> Result<Vec<(&'a str, &'a str)>, String> {
+    options
+        .map(|option| match option.split_once('=') {
+            Some((key, value)) if allowed.contains(&key) => Ok((key, value)),
+            _ => Err(format!("Unknown layer option: {}", option)),
+        })
+        .collect()
+}
+
+/// Parses a layer option, returning the default when it is not provided.
+fn layer_option<T: FromStr>(options: &[(&str, &str)], key: &str, default: T) -> Result<T, String> {
+    match options.iter().rev().find(|(k, _)| *k == key) {
+        Some((_, value)) => value
+            .parse()
+            .map_err(|_| format!("Invalid value for {}: {}", key, value)),
+        None => Ok(default),
+    }
+}
+
+/// Parses an initializer layer option, defaulting to Initializer::Auto.
+fn layer_initializer(options: &[(&str, &str)], key: &str) -> Result<Initializer, String> {
+    match options.iter().rev().find(|(k, _)| *k == key) {
+        Some((_, value)) => parse_initializer(value),
+        None => Ok(Initializer::Auto),
+    }
+}
+
+/// Parses an initializer, where file([path]) loads the values from a text file.
+fn parse_initializer(value: &str) -> Result<Initializer, String> {
+    Initializer::from_spec(value).map_err(|error| error.to_string())
+}
+
+fn train(
+    network_path: &PathBuf,
+    inputs_path: &PathBuf,
+    labels_path: &Option<PathBuf>,
+    learning_rate: &Float,
+    lr_schedule: &str,
+    thread_count: &usize,
+    batch_size: &NonZeroUsize,
+    batch_count: &Option<usize>,
+    epochs: &usize,
+    test_inputs: &Option<PathBuf>,
+    test_labels: &Option<PathBuf>,
+    regression: bool,
+    preset: Option<Preset>,
+    normalize: &Option<String>,
+    augmentation: &AugmentationArgs,
+    csv: &CsvArgs,
+    validation: &ValidationArgs,
+    regularization: &RegularizationArgs,
+) {
+    let normalizations = match normalize {
+        Some(normalize) => match normalize.split(',').map(|s| s.parse()).collect() {
+            Ok(normalizations) => Some::<Vec<Normalization>>(normalizations),
+            Err(_) => {
+                println!("Invalid normalization: {}", normalize);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let options = match regularization.training_options(*learning_rate) {
+        Ok(options) => options,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    let mut trainer = Trainer::new(options, *batch_size, *epochs);
+
+    trainer.batch_count = *batch_count;
+    trainer.restore_best = !validation.keep_last;
+    trainer.metric = match (&validation.metric, regression) {
+        (Some(metric), _) => metric.parse().expect("Invalid metric"),
+        (None, true) => Metric::Loss,
+        (None, false) => Metric::Accuracy,
+    };
+
+    if regression && trainer.metric == Metric::Accuracy {
+        println!("The accuracy metric is only supported for classification");
+        return;
+    }
+    trainer.early_stopping = validation.early_stopping.map(|patience| EarlyStopping {
+        patience,
+        min_delta: validation.min_delta,
+    });
+
+    match lr_schedule.parse() {
+        Ok(schedule) => trainer.scheduler = LearningRateScheduler::new(schedule, *learning_rate),
+        Err(_) => {
+            println!("Invalid learning rate schedule: {}", lr_schedule);
+            return;
+        }
+    };
+
+    if labels_path.is_none() && !csv.has_targets(inputs_path)
+        || test_inputs
+            .as_ref()
+            .is_some_and(|test_inputs| test_labels.is_none() && !csv.has_targets(test_inputs))
+    {
+        println!("Labels are required, unless the inputs are a CSV file with --targets");
+        return;
+    }
+
+    let has_validation = test_inputs.is_some() || validation.validation_split.is_some();
+
+    if !has_validation && (trainer.scheduler.needs_validation() || trainer.early_stopping.is_some())
+    {
+        println!("The learning rate schedule and early stopping require test inputs or a validation split");
+        return;
+    }
+
+    if *thread_count > 1 {
+        println!("Multithreaded training is not supported yet, training on a single thread");
+    }
+
+    let mut network = match io::read_network_file(network_path) {
+        Err(error) => {
+            println!("Error while reading network: {}", error);
+            return;
+        }
+        Ok(network) => network,
+    };
+
+    if let Some(preset) = preset {
+        if let Err(error) = preset.check_shape(&network.shape()) {
+            println!("{}", error);
+            return;
+        }
+
+        preset.fill_metadata(&mut network.metadata);
+    }
+
+    let training_data = match read_data(
+        &mut network,
+        inputs_path,
+        labels_path,
+        regression,
+        preset,
+        csv,
+    ) {
+        Err(error) => {
+            println!("Error while reading training data: {}", error);
+            return;
+        }
+        Ok(data) => data,
+    };
+
+    let (training_data, validation_data) = match test_inputs {
+        Some(test_inputs) => {
+            match read_data(
+                &mut network,
+                test_inputs,
+                test_labels,
+                regression,
+                preset,
+                csv,
+            ) {
+                Err(error) => {
+                    println!("Error while reading test data: {}", error);
+                    return;
+                }
+                Ok(data) => (training_data, Some(data)),
+            }
+        }
+        None => match validation.validation_split {
+            Some(fraction) => {
+                let (training_data, validation_data) = trainer::split_data(training_data, fraction);
+
+                (training_data, Some(validation_data))
+            }
+            None => (training_data, None),
+        },
+    };
+
+    if training_data.is_empty() {
+        println!("There is no training data left to train on");
+        return;
+    }
+
+    // IDX inputs are bytes, which are scaled to [0, 1] before any other preprocessing
+    let idx_inputs = !is_csv(inputs_path) && !io::is_npy_path(inputs_path);
+
+    if let Some(normalizations) = &normalizations {
+        if !network.preprocessing.is_empty() {
+            println!("Replacing the existing preprocessing of the network");
+        }
+
+        let mut preprocessing = match idx_inputs {
+            true => idx_preprocessing(),
+            false => Preprocessing::default(),
+        };
+
+        let inputs: Vec<Vec<Float>> = training_data
+            .iter()
+            .map(|(input, _)| preprocessing.transform(input.clone()))
+            .collect();
+
+        preprocessing
+            .transforms
+            .extend(Preprocessing::fit(normalizations, &inputs).transforms);
+
+        network.preprocessing = preprocessing;
+    } else if idx_inputs && network.preprocessing.is_empty() {
+        network.preprocessing = idx_preprocessing();
+    }
+
+    let augmenter = match augmentation.augmenter(&network) {
+        Ok(augmenter) => augmenter,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    // Augmented inputs are preprocessed at every epoch, IDX bytes are scaled to [0, 1] before augmentation
+    let training_data = match augmenter {
+        Some(mut augmenter) => {
+            let mut preprocessing = network.preprocessing.clone();
+            let mut scale = Preprocessing::default();
+
+            if idx_inputs
+                && preprocessing.transforms.first() == idx_preprocessing().transforms.first()
+            {
+                scale.transforms.push(preprocessing.transforms.remove(0));
+            }
+
+            trainer.set_input_transform(move |input| {
+                let input = scale.transform(input.to_vec());
+
+                preprocessing.transform(augmenter.augment(&input))
+            });
+
+            training_data
+        }
+        None => preprocess(&network, training_data),
+    };
+    let validation_data = validation_data.map(|data| preprocess(&network, data));
+
+    trainer.add_callback(Progress {
+        batch_size: batch_size.get(),
+        batch_count: *batch_count,
+        samples: training_data.len(),
+        metric: trainer.metric,
+        terminal: std::io::stdout().is_terminal(),
+        percentage: None,
+    });
+
+    println!();
+
+    let result = trainer.train(&mut network, &training_data, validation_data.as_deref());
+
+    if result.stopped_early {
+        println!(
+            "Stopped early, the validation {} didn't improve",
+            trainer.metric
+        );
+    }
+
+    if let (Some(best_epoch), Some(best_validation)) = (result.best_epoch, result.best_validation) {
+        if trainer.restore_best && best_epoch + 1 != result.epochs {
+            println!(
+                "Restored the network from epoch {} with validation {} {}",
+                best_epoch + 1,
+                trainer.metric,
+                format_metric(trainer.metric, best_validation)
+            );
+        }
+    }
+
+    let final_epoch = match result.best_epoch {
+        Some(best_epoch) if trainer.restore_best => result.history.get(best_epoch),
+        _ => result.history.last(),
+    };
+
+    network.metadata.input_normalization = network.preprocessing.description();
+    network.metadata.training.push(TrainingRun {
+        dataset: inputs_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        epochs: result.epochs,
+        loss: final_epoch.map(|epoch| epoch.loss),
+        accuracy: match (&validation_data, regression) {
+            (Some(validation_data), false) => {
+                Some(Metric::Accuracy.evaluate(&network, validation_data))
+            }
+            _ => None,
+        },
+    });
+
+    let encoded = match network.encode() {
+        Ok(encoded) => encoded,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    match io::write_file(&encoded, network_path, false, Some(FILE_EXTENSION)) {
+        Err(error) => {
+            println!(
+                "Error while saving network to {}: {}",
+                network_path.display(),
+                error
+            );
+            return;
+        }
+        Ok(_) => println!(
+            "Finished training. Saved the neural network to {}",
+            network_path.display()
+        ),
+    };
+}
+
+/// Pairs of inputs and expected outputs
+type Samples = Vec<(Vec<Float>, Vec<Float>)>;
+
+/// Inputs and their class labels
+type LabeledData = (Vec<Vec<Float>>, Vec<Vec<u8>>);
+
+/// Prints the progress of every epoch, with a progress bar of the batches when writing to a terminal
+struct Progress {
+    batch_size: usize,
+    batch_count: Option<usize>,
+    samples: usize,
+    metric: Metric,
+    terminal: bool,
+    /// The last printed percentage of the progress bar
+    percentage: Option<usize>,
+}
+
+impl Callback for Progress {
+    fn on_epoch_begin(&mut self, epoch: usize, learning_rate: Float) {
+        println!(
+            "Starting epoch {} training with {} batches of size {} and {} total samples",
+            epoch + 1,
+            match self.batch_count {
+                Some(batch_count) => batch_count.to_string(),
+                None => "all".to_string(),
+            },
+            self.batch_size,
+            match self.batch_count {
+                Some(batch_count) => (self.batch_size * batch_count).min(self.samples),
+                None => self.samples,
+            }
+        );
+        println!("Learning rate: {}", learning_rate);
+
+        self.percentage = None;
+    }
+
+    fn on_batch_end(&mut self, result: &BatchResult) -> ControlFlow<()> {
+        let percentage = (result.batch + 1) * 100 / result.batch_count;
+
+        if self.terminal && self.percentage != Some(percentage) {
+            let width = 30;
+            let filled = percentage * width / 100;
+
+            print!(
+                "\r[{}{}] {}/{} batches, loss {:.6}",
+                "=".repeat(filled),
+                " ".repeat(width - filled),
+                result.batch + 1,
+                result.batch_count,
+                result.loss
+            );
+            let _ = std::io::stdout().flush();
+
+            self.percentage = Some(percentage);
+        }
+
+        return ControlFlow::Continue(());
+    }
+
+    fn on_epoch_end(&mut self, _network: &Network, result: &EpochResult) -> ControlFlow<()> {
+        if self.percentage.is_some() {
+            println!();
+        }
+
+        println!("Finished training for epoch {}.", result.epoch + 1);
+        println!("Loss: {:.6}", result.loss);
+
+        if let (Some(loss), Metric::Accuracy) = (result.validation_loss, self.metric) {
+            println!("Validation loss: {:.6}", loss);
+        }
+
+        if let Some(value) = result.validation {
+            println!(
+                "Validation {}: {}{}",
+                self.metric,
+                format_metric(self.metric, value),
+                if result.improved { " (best)" } else { "" }
+            );
+        }
+
+        println!();
+
+        return ControlFlow::Continue(());
+    }
+}
+
+/// Reads inputs and labels as training data, with IDX inputs as raw bytes that the preprocessing scales to [0, 1].
+/// For regression, the labels are targets that are read as floats from an IDX or CSV file.
+/// Without a labels file, the targets are the target columns of the CSV inputs.
+fn read_data(
+    network: &mut Network,
+    inputs_path: &PathBuf,
+    labels_path: &Option<PathBuf>,
+    regression: bool,
+    preset: Option<Preset>,
+    csv: &CsvArgs,
+) -> Result<Samples, neural_utils::Error> {
+    if !regression {
+        let (inputs, labels) = read_labeled_data(network, inputs_path, labels_path, preset, csv)?;
+
+        // If expected output is a Vec with a single item, it is seen as an index. If it has multiple items, it is seen as an output.
+        return Ok(inputs
+            .into_iter()
+            .zip(outputs_from_labels(network, labels))
+            .collect());
+    }
+
+    let (inputs, targets) = match labels_path {
+        Some(labels_path) => (
+            read_inputs(inputs_path, preset, csv)?,
+            io::read_values_file(labels_path)?,
+        ),
+        None => {
+            let dataset = csv::read_csv_file(inputs_path, &csv.options(false, &[]))?;
+
+            (dataset.inputs, dataset.targets)
+        }
+    };
+
+    let output_size = *network.shape().last().unwrap_or(&0);
+
+    if let Some(target) = targets.iter().find(|target| target.len() != output_size) {
+        return Err(neural_utils::Error::Decode(format!(
+            "Targets have {} values, but the network has {} outputs",
+            target.len(),
+            output_size
+        )));
+    }
+
+    check_sample_count(inputs.len(), targets.len())?;
+
+    return Ok(inputs.into_iter().zip(targets).collect());
+}
+
+/// Reads inputs and class labels, from an IDX labels file, a NumPy array of class indices or the target column of CSV inputs.
+/// Class names in CSV files are mapped to the class names of the network, which are set if the network has none.
+fn read_labeled_data(
+    network: &mut Network,
+    inputs_path: &PathBuf,
+    labels_path: &Option<PathBuf>,
+    preset: Option<Preset>,
+    csv: &CsvArgs,
+) -> Result<LabeledData, neural_utils::Error> {
+    let (inputs, labels) = match labels_path {
+        Some(labels_path) if io::is_npy_path(labels_path) => {
+            let labels = io::read_npy_array(labels_path)?;
+
+            if labels.shape.len() > 2 || labels.shape.get(1).is_some_and(|size| *size != 1) {
+                return Err(neural_utils::Error::Decode(format!(
+                    "NumPy labels should be class indices, but they have the shape {:?}",
+                    labels.shape
+                )));
+            }
+
+            let labels = labels
+                .values
+                .iter()
+                .map(
+                    |label| match label.fract() == 0.0 && (0.0..256.0).contains(label) {
+                        true => Ok(vec![*label as u8]),
+                        false => Err(neural_utils::Error::Decode(format!(
+                            "{} isn't a class index",
+                            label
+                        ))),
+                    },
+                )
+                .collect::<Result<Vec<_>, _>>()?;
+
+            (read_inputs(inputs_path, preset, csv)?, labels)
+        }
+        Some(labels_path) => {
+            let labels = io::read_idx_file(labels_path)?.items;
+            let labels = match preset {
+                Some(preset) => preset.labels(labels)?,
+                None => labels,
+            };
+
+            (read_inputs(inputs_path, preset, csv)?, labels)
+        }
+        None => {
+            let options = csv.options(true, &network.metadata.class_names);
+            let dataset = csv::read_csv_file(inputs_path, &options)?;
+            let output_size = *network.shape().last().unwrap_or(&0);
+
+            if dataset.classes.len() > output_size.min(256) {
+                return Err(neural_utils::Error::Decode(format!(
+                    "There are {} classes, but the network has {} outputs",
+                    dataset.classes.len(),
+                    output_size
+                )));
+            }
+
+            if network.metadata.class_names.is_empty() {
+                network.metadata.class_names = dataset.classes;
+            }
+
+            let labels = dataset
+                .targets
+                .iter()
+                .map(|target| vec![target[0] as u8])
+                .collect();
+
+            (dataset.inputs, labels)
+        }
+    };
+
+    check_sample_count(inputs.len(), labels.len())?;
+
+    return Ok((inputs, labels));
+}
+
+fn check_sample_count(inputs: usize, labels: usize) -> Result<(), neural_utils::Error> {
+    if inputs != labels {
+        return Err(neural_utils::Error::Decode(format!(
+            "There are {} inputs, but {} labels",
+            inputs, labels
+        )));
+    }
+
+    return Ok(());
+}
+
+fn is_csv(path: &Path) -> bool {
+    return path.extension().is_some_and(|x| x == "csv");
+}
+
+/// Scales IDX images to [0, 1], and transposes them if the dataset preset stores them transposed
+fn read_images(images: Vec<Vec<u8>>, preset: Option<Preset>) -> Vec<Vec<Float>> {
+    return match preset {
+        Some(preset) => preset.inputs(images),
+        None => inputs_from_bytes(images),
+    };
+}
+
+fn parse_preset(preset: &Option<String>) -> Option<Preset> {
+    return preset
+        .as_ref()
+        .map(|preset| preset.parse().expect("Invalid preset"));
+}
+
+/// Applies the preprocessing of the network to the inputs
+fn preprocess(network: &Network, data: Samples) -> Samples {
+    return data
+        .into_iter()
+        .map(|(input, output)| (network.preprocessing.transform(input), output))
+        .collect();
+}
+
+fn format_metric(metric: Metric, value: Float) -> String {
+    match metric {
+        Metric::Accuracy => format!("{:.2}%", value * 100.0),
+        Metric::Loss => format!("{:.6}", value),
+    }
+}
+
+fn test(
+    network_path: &PathBuf,
+    inputs_path: &PathBuf,
+    labels_path: &Option<PathBuf>,
+    count: &Option<usize>,
+    regression: bool,
+    preset: Option<Preset>,
+    csv: &CsvArgs,
+    report_args: &ReportArgs,
+    verbose: bool,
+) {
+    let mut network = match io::read_network_file(network_path) {
+        Err(error) => {
+            println!("Error while reading network: {}", error);
+            return;
+        }
+        Ok(network) => network,
+    };
+
+    if labels_path.is_none() && !csv.has_targets(inputs_path) {
+        println!("Labels are required, unless the inputs are a CSV file with --targets");
+        return;
+    }
+
+    if regression {
+        test_regression(&mut network, inputs_path, labels_path, count, csv);
+        return;
+    }
+
+    if let Some(Err(error)) = preset.map(|preset| preset.check_shape(&network.shape())) {
+        println!("{}", error);
+        return;
+    }
+
+    let (inputs, labels) =
+        match read_labeled_data(&mut network, inputs_path, labels_path, preset, csv) {
+            Err(error) => {
+                println!("Error while reading test data: {}", error);
+                return;
+            }
+            Ok(data) => data,
+        };
+
+    if labels.iter().any(|label| label.len() != 1) {
+        println!("Labels should be a single class index, use --task regression for targets with multiple values");
+        return;
+    }
+
+    let count = count.unwrap_or(inputs.len());
+
+    let mut test_data: Vec<(Vec<Float>, Vec<u8>)> = inputs.into_iter().zip(labels).collect();
+    let mut rng = rand::thread_rng();
+
+    test_data.shuffle(&mut rng);
+    test_data.truncate(count);
+
+    let accuracy = test_only(&network, &test_data, verbose);
+
+    let (inputs, labels): (Vec<Vec<Float>>, Vec<Vec<u8>>) = test_data.into_iter().unzip();
+    let loss_data = preprocess(
+        &network,
+        inputs
+            .iter()
+            .cloned()
+            .zip(outputs_from_labels(&network, labels.clone()))
+            .collect(),
+    );
+
+    println!("Accuracy: {:.2}%", accuracy * 100.0);
+    println!("Loss: {:.6}", network.evaluate_loss(&loss_data));
+
+    if report_args.report {
+        report(&network, &inputs, &labels, report_args);
+    }
+}
+
+/// Prints or writes the classification report of the network
+fn report(network: &Network, inputs: &[Vec<Float>], labels: &[Vec<u8>], args: &ReportArgs) {
+    let data: Vec<(Vec<Float>, usize)> = inputs
+        .iter()
+        .cloned()
+        .zip(labels.iter().map(|label| usize::from(label[0])))
+        .collect();
+
+    let mut report = ClassificationReport::evaluate(network, &data, &args.top_k);
+
+    match &args.class_names {
+        Some(class_names) => {
+            let class_names: Vec<String> = class_names.split(',').map(|s| s.to_string()).collect();
+
+            report = report.with_labels(&class_names);
         }
-        Ok(_) => println!("Created a new neural network at {}", network_path.display()),
+        None => report = report.with_labels(&network.metadata.class_names),
+    }
+
+    let output = match args.report_format.as_str() {
+        "csv" => report.to_csv(),
+        "json" => match serde_json::to_string_pretty(&report) {
+            Ok(json) => json,
+            Err(error) => {
+                println!("Error while encoding report: {}", error);
+                return;
+            }
+        },
+        _ => report.to_string(),
     };
+
+    match &args.report_output {
+        Some(path) => match std::fs::write(path, output) {
+            Ok(_) => println!("Saved the classification report to {}", path.display()),
+            Err(error) => println!("Error while writing report: {}", error),
+        },
+        None => {
+            println!();
+            println!("{}", output);
+        }
+    }
 }
 
-fn train(
-    network_path: &PathBuf,
+fn test_only(network: &Network, test_batch: &[(Vec<Float>, Vec<u8>)], verbose: bool) -> Float {
+    let mut accuracy = 0.0;
+
+    for (input, label) in test_batch {
+        let result = network.predict(input.clone());
+
+        let label = label[0];
+        let (result, probability) = result
+            .iter()
+            .enumerate()
+            .max_by(|(_, p1), (_, p2)| p1.partial_cmp(p2).unwrap())
+            .unwrap();
+
+        let names = &network.metadata;
+
+        if result != usize::from(label) {
+            if verbose {
+                println!(
+                    "Wrong: {} = {} @ {:.2}%",
+                    names.class_name(usize::from(label)),
+                    names.class_name(result),
+                    probability * 100.0
+                );
+            }
+        } else {
+            if verbose {
+                println!(
+                    "Correct: {} = {} @ {:.2}%",
+                    names.class_name(usize::from(label)),
+                    names.class_name(result),
+                    probability * 100.0
+                );
+            }
+
+            accuracy += 1.0;
+        }
+    }
+
+    return accuracy / test_batch.len() as Float;
+}
+
+/// Tests a network on regression targets, printing the regression metrics
+fn test_regression(
+    network: &mut Network,
     inputs_path: &PathBuf,
-    labels_path: &PathBuf,
-    learning_rate: &Float,
-    thread_count: &usize,
-    batch_size: &usize,
-    batch_count: &Option<usize>,
-    epochs: &usize,
-    test_inputs: &Option<PathBuf>,
-    test_labels: &Option<PathBuf>,
-    verbose: bool,
+    targets_path: &Option<PathBuf>,
+    count: &Option<usize>,
+    csv: &CsvArgs,
 ) {
-    let mut network = match io::read_network_file(network_path) {
+    let mut test_data = match read_data(network, inputs_path, targets_path, true, None, csv) {
         Err(error) => {
-            println!("Error while reading network: {}", error);
+            println!("Error while reading test data: {}", error);
+            return;
+        }
+        Ok(data) => data,
+    };
+
+    test_data.shuffle(&mut rand::thread_rng());
+    test_data.truncate(count.unwrap_or(test_data.len()));
+
+    println!("{}", RegressionReport::evaluate(network, &test_data));
+    println!(
+        "Loss: {:.6}",
+        network.evaluate_loss(&preprocess(network, test_data))
+    );
+}
+
+fn evaluate(network_path: &PathBuf, input_path: &PathBuf, output_path: &Option<PathBuf>) {
+    let network = match io::read_network_file(network_path) {
+        Err(error) => {
+            println!("{}", error);
             return;
         }
         Ok(network) => network,
     };
 
-    let inputs: Vec<Vec<_>> = match io::read_idx_file(inputs_path) {
+    let input_data: Vec<Float> = match io::read_file(input_path) {
         Err(error) => {
-            println!("Error while reading inputs: {}", error);
+            println!("Error while reading input: {}", error);
             return;
         }
-        Ok(idx) => idx.items,
+        Ok(input) => inputs_from_bytes(vec![input]).remove(0),
     };
 
-    let labels = match io::read_idx_file(labels_path) {
+    let output = match network.try_predict(input_data) {
         Err(error) => {
-            println!("Error while reading labels: {}", error);
+            println!("Error while evaluating input: {}", error);
             return;
         }
-        Ok(idx) => idx.items,
+        Ok(output) => output,
     };
 
-    let test_inputs: Option<Vec<Vec<_>>> = match test_inputs {
-        Some(test_inputs) => match io::read_idx_file(test_inputs) {
-            Err(error) => {
-                println!("Error while reading test inputs: {}", error);
-                return;
+    if let Some(output_path) = output_path {
+        match io::write_file(
+            &output.into_iter().map(|x| (x * 255.0) as u8).collect(),
+            output_path,
+            true,
+            None,
+        ) {
+            Err(error) => println!("Error while saving output: {}", error),
+            Ok(_) => println!("Saved output to {}", output_path.display()),
+        }
+    } else {
+        let class_names = &network.metadata.class_names;
+
+        for (i, p) in output.iter().enumerate() {
+            match class_names.get(i) {
+                Some(name) => println!("{:>2}: {:.2}%", name, p * 100.0),
+                None => println!("{:>2}: {:.2}%", i + 1, p * 100.0),
             }
-            Ok(idx) => Some(idx.items),
-        },
-        None => None,
+        }
+    }
+}
+
+fn predict(
+    network_path: &PathBuf,
+    inputs_path: &PathBuf,
+    output_path: &PathBuf,
+    mode: &str,
+    top_k: usize,
+    format: &Option<String>,
+    batch_size: usize,
+    thread_count: usize,
+    preset: Option<Preset>,
+    csv: &CsvArgs,
+) {
+    let network = match io::read_network_file(network_path) {
+        Err(error) => {
+            println!("Error while reading network: {}", error);
+            return;
+        }
+        Ok(network) => network,
     };
 
-    let test_labels: Option<Vec<Vec<_>>> = match test_labels {
-        Some(test_labels) => match io::read_idx_file(test_labels) {
-            Err(error) => {
-                println!("Error while reading test labels: {}", error);
+    let inputs = match read_inputs(inputs_path, preset, csv) {
+        Err(error) => {
+            println!("Error while reading inputs: {}", error);
+            return;
+        }
+        Ok(inputs) => inputs,
+    };
+
+    if let Some(error) = inputs
+        .iter()
+        .find_map(|input| network.check_input(input).err())
+    {
+        println!("Error while reading inputs: {}", error);
+        return;
+    }
+
+    let format = match format {
+        Some(format) => format.as_str(),
+        None => match output_path.extension().and_then(|x| x.to_str()) {
+            Some("csv") => "csv",
+            Some("jsonl") => "jsonl",
+            Some("idx") => "idx",
+            Some("npy") => "npy",
+            _ => {
+                println!("Unknown output format, specify it with --format or use a .csv, .jsonl, .idx or .npy extension");
                 return;
             }
-            Ok(idx) => Some(idx.items),
         },
-        None => None,
     };
 
-    let inputs_len = inputs.len();
+    let output_size = *network.shape().last().unwrap_or(&0);
+    let k = match mode {
+        "argmax" => 1,
+        "top-k" => top_k.min(output_size),
+        _ => output_size,
+    };
+
+    if thread_count > 1 && !cfg!(feature = "threads") {
+        println!("Multithreading is not enabled, predicting on a single thread");
+    }
 
-    let training_data: Vec<(Vec<Float>, Vec<Float>)> = inputs
-        .into_iter()
-        .map(|input| {
-            input
-                .into_iter()
-                .map(|x| Float::from(x) / 255.0)
-                .collect::<Vec<_>>()
-        })
-        // If expected output is a Vec with a single item, it is seen as an index. If it has multiple items, it is seen as an output.
-        .zip(outputs_from_labels(&network, labels).into_iter())
-        .collect();
+    let result = std::fs::File::create(output_path).and_then(|file| {
+        let mut writer = std::io::BufWriter::new(file);
+
+        write_prediction_header(&mut writer, format, mode, k, inputs.len())?;
 
-    let mut test_data = match (test_inputs, test_labels) {
-        (Some(test_inputs), Some(test_labels)) => Some(
-            test_inputs
-                .into_iter()
-                .zip(test_labels.into_iter())
-                .collect::<Vec<(Vec<u8>, Vec<u8>)>>(),
+        for batch in inputs.chunks(batch_size.max(1)) {
+            let batch: Vec<Vec<Float>> = batch
+                .iter()
+                .map(|input| network.preprocessing.transform(input.clone()))
+                .collect();
+
+            for output in feed_forward_batch(&network, &batch, thread_count) {
+                write_prediction(&mut writer, format, mode, k, output)?;
+            }
+        }
+
+        writer.flush()
+    });
+
+    match result {
+        Err(error) => println!("Error while saving predictions: {}", error),
+        Ok(_) => println!(
+            "Saved the predictions for {} samples to {}",
+            inputs.len(),
+            output_path.display()
         ),
-        _ => None,
-    };
+    }
+}
 
-    let mut rng = rand::thread_rng();
+/// Reads inputs from the feature columns of a CSV file, a NumPy array, or an IDX file as raw bytes, which the preprocessing of the network scales.
+fn read_inputs(
+    path: &PathBuf,
+    preset: Option<Preset>,
+    csv: &CsvArgs,
+) -> Result<Vec<Vec<Float>>, neural_utils::Error> {
+    if is_csv(path) {
+        return Ok(csv::read_csv_file(path, &csv.input_options())?.inputs);
+    }
 
-    println!();
+    if io::is_npy_path(path) {
+        return Ok(io::read_npy_array(path)?.items());
+    }
 
-    for i in 0..*epochs {
-        let mut training_data: Vec<(Vec<Float>, Vec<Float>)> = training_data.clone();
+    return Ok(read_images(io::read_idx_file(path)?.items, preset));
+}
 
-        training_data.shuffle(&mut rng);
+#[cfg(feature = "threads")]
+fn feed_forward_batch(
+    network: &Network,
+    inputs: &[Vec<Float>],
+    thread_count: usize,
+) -> Vec<Vec<Float>> {
+    if thread_count > 1 {
+        return network.parallel_feed_forward_batch(inputs, thread_count);
+    }
 
-        training_data = training_data
-            .into_iter()
-            .take(match batch_count {
-                Some(batch_count) => batch_size * batch_count,
-                None => inputs_len,
-            })
-            .collect();
+    return network.feed_forward_batch(inputs);
+}
 
-        println!(
-            "Starting epoch {} training with {} batches of size {} and {} total samples",
-            i + 1,
-            match batch_count {
-                Some(batch_count) => batch_count.to_string(),
-                None => "all".to_string(),
-            },
-            batch_size,
-            training_data.len()
-        );
+#[cfg(not(feature = "threads"))]
+fn feed_forward_batch(
+    network: &Network,
+    inputs: &[Vec<Float>],
+    _thread_count: usize,
+) -> Vec<Vec<Float>> {
+    return network.feed_forward_batch(inputs);
+}
 
-        if thread_count > &1 {
-            if cfg!(feature = "threads") {
-                #[cfg(feature = "threads")]
-                network.parallel_stochastic_gradient_descent(
-                    training_data,
-                    *thread_count,
-                    *batch_size,
-                    *learning_rate,
-                );
+/// The indices and values of the k largest outputs, largest first
+fn largest_outputs(output: &[Float], k: usize) -> Vec<(usize, Float)> {
+    let mut outputs: Vec<(usize, Float)> = output.iter().copied().enumerate().collect();
+    outputs.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    outputs.truncate(k);
+
+    return outputs;
+}
+
+fn write_prediction_header(
+    writer: &mut impl Write,
+    format: &str,
+    mode: &str,
+    k: usize,
+    count: usize,
+) -> std::io::Result<()> {
+    match (format, mode) {
+        ("csv", "outputs") => writeln!(
+            writer,
+            "{}",
+            (0..k)
+                .map(|i| format!("output_{}", i))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        ("csv", "argmax") => writeln!(writer, "class,output"),
+        ("csv", _) => writeln!(
+            writer,
+            "{}",
+            (1..=k)
+                .map(|i| format!("class_{},output_{}", i, i))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        ("idx" | "npy", mode) => {
+            let shape = match mode {
+                "outputs" => vec![count, k],
+                "argmax" => vec![count],
+                _ => vec![count, k, 2],
+            };
+
+            if format == "npy" {
+                writer.write_all(&npy::encode_npy_header(&shape))
             } else {
-                panic!("Threads not supported!");
+                let shape: Vec<u32> = shape.into_iter().map(|x| x as u32).collect();
+
+                writer.write_all(&idx::encode_f32_header(&shape))
             }
-        } else {
-            network.stochastic_gradient_descent(training_data, *batch_size, *learning_rate);
         }
+        _ => Ok(()),
+    }
+}
 
-        println!("Finished training for epoch {}.", i + 1);
-
-        if let Some(test_data) = &mut test_data {
-            test_data.shuffle(&mut rng);
-            let accuracy = test_only(&network, test_data, verbose);
+fn write_prediction(
+    writer: &mut impl Write,
+    format: &str,
+    mode: &str,
+    k: usize,
+    output: Vec<Float>,
+) -> std::io::Result<()> {
+    let largest = largest_outputs(&output, k);
+
+    match (format, mode) {
+        ("csv", "outputs") => writeln!(
+            writer,
+            "{}",
+            output
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        ("csv", _) => writeln!(
+            writer,
+            "{}",
+            largest
+                .iter()
+                .map(|(class, x)| format!("{},{}", class, x))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        ("jsonl", "outputs") => writeln!(writer, "{}", serde_json::json!({ "outputs": output })),
+        ("jsonl", "argmax") => writeln!(
+            writer,
+            "{}",
+            serde_json::json!({ "class": largest[0].0, "output": largest[0].1 })
+        ),
+        ("jsonl", _) => writeln!(
+            writer,
+            "{}",
+            serde_json::json!({
+                "classes": largest.iter().map(|(class, _)| class).collect::<Vec<_>>(),
+                "outputs": largest.iter().map(|(_, x)| x).collect::<Vec<_>>(),
+            })
+        ),
+        (format, mode) => {
+            let values: Vec<Float> = match mode {
+                "outputs" => output,
+                "argmax" => vec![largest[0].0 as Float],
+                _ => largest
+                    .iter()
+                    .flat_map(|(class, x)| [*class as Float, *x])
+                    .collect(),
+            };
 
-            println!("Accuracy: {:.2}%", accuracy * 100.0);
+            values.iter().try_for_each(|x| match format {
+                "npy" => writer.write_all(&x.to_le_bytes()),
+                _ => writer.write_f32::<BigEndian>(*x as f32),
+            })
         }
-
-        println!();
     }
+}
 
-    let encoded: Vec<u8> = bincode::encode_to_vec(network, bincode::config::standard()).expect("Unable to encode network");
-
-    match io::write_file(&encoded, network_path, false, Some(FILE_EXTENSION)) {
+fn export(network_path: &PathBuf, output_path: &PathBuf) {
+    let network = match io::read_network_file(network_path) {
         Err(error) => {
-            println!(
-                "Error while saving network to {}: {}",
-                network_path.display(),
-                error
-            );
+            println!("Error while reading network: {}", error);
             return;
         }
+        Ok(network) => network,
+    };
+
+    let arrays = npy::network_weights(&network);
+
+    if arrays.is_empty() {
+        println!("The network doesn't have fully connected layers");
+        return;
+    }
+
+    let result = npy::encode_npz(&arrays)
+        .and_then(|encoded| io::write_file(&encoded, output_path, true, Some("npz")));
+
+    match result {
+        Err(error) => println!("Couldn't write to {}: {}", output_path.display(), error),
         Ok(_) => println!(
-            "Finished training. Saved the neural network to {}",
-            network_path.display()
+            "Exported {} arrays to {}",
+            arrays.len(),
+            output_path.display()
         ),
-    };
+    }
 }
 
-fn test(
-    network_path: &PathBuf,
-    inputs_path: &PathBuf,
-    labels_path: &PathBuf,
-    count: &Option<usize>,
-    verbose: bool,
-) {
-    let network = match io::read_network_file(network_path) {
+fn import(network_path: &PathBuf, weights_path: &PathBuf) {
+    let mut network = match io::read_network_file(network_path) {
         Err(error) => {
             println!("Error while reading network: {}", error);
             return;
@@ -486,134 +2207,72 @@ fn test(
         Ok(network) => network,
     };
 
-    let inputs: Vec<Vec<u8>> = match io::read_idx_file(inputs_path) {
+    let arrays = match io::read_file(weights_path).and_then(|data| npy::parse_npz(&data)) {
         Err(error) => {
-            println!("Error while reading inputs: {}", error);
+            println!("Error while reading weights: {}", error);
             return;
         }
-        Ok(idx) => idx.items,
+        Ok(arrays) => arrays,
     };
 
-    let labels: Vec<Vec<u8>> = match io::read_idx_file(labels_path) {
+    let loaded = match npy::load_network_weights(&mut network, &arrays) {
         Err(error) => {
-            println!("Error while reading labels: {}", error);
+            println!("{}", error);
             return;
         }
-        Ok(idx) => idx.items,
+        Ok(loaded) => loaded,
     };
 
-    let count = count.unwrap_or(inputs.len());
-
-    let mut test_data: Vec<(Vec<u8>, Vec<u8>)> =
-        inputs.iter().cloned().zip(labels.iter().cloned()).collect();
-    let mut rng = rand::thread_rng();
-
-    test_data.shuffle(&mut rng);
-
-    let test_batch: Vec<(Vec<u8>, Vec<u8>)> = test_data.iter().take(count).cloned().collect();
-
-    let accuracy = test_only(&network, &test_batch, verbose);
-
-    println!("Accuracy: {:.2}%", accuracy * 100.0);
-}
-
-fn test_only(network: &Network, test_batch: &[(Vec<u8>, Vec<u8>)], verbose: bool) -> Float {
-    let mut accuracy = 0.0;
-
-    for (input, label) in test_batch {
-        let pixels: Vec<Float> = input.iter().map(|x| Float::from(*x) / 255.0).collect();
-
-        let result = network.feed_forward(pixels);
-
-        if label.len() == 1 {
-            let label = label[0];
-            let (result, probability) = result
-                .iter()
-                .enumerate()
-                .max_by(|(_, p1), (_, p2)| p1.partial_cmp(p2).unwrap())
-                .unwrap();
-
-            if result != usize::from(label) {
-                if verbose {
-                    println!(
-                        "Wrong: {} = {} @ {:.2}%",
-                        label,
-                        result,
-                        probability * 100.0
-                    );
-                }
-            } else {
-                if verbose {
-                    println!(
-                        "Correct: {} = {} @ {:.2}%",
-                        label,
-                        result,
-                        probability * 100.0
-                    );
-                }
-
-                accuracy += 1.0;
-            }
-        } else {
-            let expected_output = outputs_from_labels(network, vec![label.clone()])
-                .into_iter()
-                .next()
-                .unwrap();
-
-            let output_len = expected_output.len();
-
-            accuracy += result
-                .into_iter()
-                .zip(expected_output.into_iter())
-                .map(|(r, e)| (r - e).abs()) // Calculate the absolute error
-                .fold(1.0, |a, x| a - (x / output_len as Float)); // Calculate the accuracy with the average error
+    let encoded = match network.encode() {
+        Ok(encoded) => encoded,
+        Err(error) => {
+            println!("{}", error);
+            return;
         }
-    }
+    };
 
-    return accuracy / test_batch.len() as Float;
+    match io::write_file(&encoded, network_path, false, Some(FILE_EXTENSION)) {
+        Err(error) => println!("Couldn't write to {}: {}", network_path.display(), error),
+        Ok(_) => println!("Imported {} arrays into {}", loaded, network_path.display()),
+    }
 }
 
-fn evaluate(network_path: &PathBuf, input_path: &PathBuf, output_path: &Option<PathBuf>) {
+fn info(network_path: &PathBuf, format: &str, sample: &Option<PathBuf>, sample_count: &usize) {
     let network = match io::read_network_file(network_path) {
         Err(error) => {
-            println!("{}", error);
+            println!("Error while reading network: {}", error);
             return;
         }
         Ok(network) => network,
     };
 
-    let input_data: Vec<Float> = match io::read_file(input_path) {
-        Err(error) => {
-            println!("Error while reading input: {}", error);
-            return;
-        }
-        Ok(network) => network.iter().map(|x| Float::from(*x) / 255.0).collect(),
+    let sample: Vec<Vec<Float>> = match sample {
+        Some(sample) => match io::read_idx_file(sample) {
+            Err(error) => {
+                println!("Error while reading sample: {}", error);
+                return;
+            }
+            Ok(idx) => inputs_from_bytes(idx.items.into_iter().take(*sample_count).collect()),
+        },
+        None => vec![],
     };
 
-    if input_data.len() != *network.shape().first().unwrap_or(&0) {
-        println!(
-            "Incorrect input data length ({}) should be {}",
-            input_data.len(),
-            network.shape().first().unwrap_or(&0)
-        );
+    if let Some(error) = sample
+        .iter()
+        .find_map(|input| network.check_input(input).err())
+    {
+        println!("Error while reading sample: {}", error);
         return;
     }
 
-    let output = network.feed_forward(input_data);
+    let summary = network.summary(&sample);
 
-    if let Some(output_path) = output_path {
-        match io::write_file(
-            &output.into_iter().map(|x| (x * 255.0) as u8).collect(),
-            output_path,
-            true,
-            None,
-        ) {
-            Err(error) => println!("Error while saving output: {}", error),
-            Ok(_) => println!("Saved output to {}", output_path.display()),
+    if format == "json" {
+        match serde_json::to_string_pretty(&summary) {
+            Ok(json) => println!("{}", json),
+            Err(error) => println!("Error while encoding summary: {}", error),
         }
     } else {
-        for (i, p) in output.iter().enumerate() {
-            println!("{:>2}: {:.2}%", i + 1, p * 100.0);
-        }
+        println!("{}", summary);
     }
 }
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 mod utils;
 
 use neural::Float;
@@ -15,13 +17,12 @@ pub struct Network {
 
 #[wasm_bindgen]
 impl Network {
+    /// Decodes a network, throwing an error if the bytes aren't a valid network.
     #[wasm_bindgen(constructor)]
-    pub fn new(network: &[u8]) -> Self {
-        return Self {
-            network: match bincode::decode_from_slice(network, bincode::config::standard()) {
-                Err(error) => panic!("Couldn't parse network: {}", error),
-                Ok((network, _)) => network,
-            },
+    pub fn new(network: &[u8]) -> Result<Network, JsValue> {
+        return match neural::network::Network::decode(network) {
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+            Ok(network) => Ok(Self { network }),
         };
     }
 
@@ -33,10 +34,33 @@ impl Network {
         return self.network.shape();
     }
 
-    /// Feeds forward the input through the network and returns the output.
+    /// Returns the description of the network, if it has one.
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> Option<String> {
+        return self.network.metadata.description.clone();
+    }
+
+    /// Returns the dataset preset the network was trained with, e.g. emnist-letters, if it has one.
+    /// Networks with a preset read images upright, others were trained on transposed EMNIST images.
+    #[wasm_bindgen(getter)]
+    pub fn preset(&self) -> Option<String> {
+        return self.network.metadata.preset.clone();
+    }
+
+    /// Returns the name of an output, or undefined if the network doesn't name its outputs.
+    pub fn class_name(&self, index: usize) -> Option<String> {
+        return self.network.metadata.class_names.get(index).cloned();
+    }
+
+    /// Applies the preprocessing of the network to the input, feeds it forward and returns the output.
+    /// Images are raw pixel intensities from 0 to 255, like the bytes of IDX images.
     ///
     /// **Return value:** Float64Array containing the output.
-    pub fn feed_forward(&self, input: &[Float]) -> Box<[Float]> {
-        return self.network.feed_forward(input.to_vec()).into_boxed_slice();
+    pub fn feed_forward(&self, input: &[Float]) -> Result<Box<[Float]>, JsValue> {
+        return self
+            .network
+            .try_predict(input.to_vec())
+            .map(|output| output.into_boxed_slice())
+            .map_err(|error| JsValue::from_str(&error.to_string()));
     }
 }
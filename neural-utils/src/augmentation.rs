@@ -0,0 +1,356 @@
+use neural::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Normal};
+use std::str::FromStr;
+
+/// A random transform of single channel images, applied to every training sample on the fly
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Augmentation {
+    /// Shifts by up to the amount of pixels horizontally and vertically
+    Shift(Float),
+    /// Rotates around the center by up to the amount of degrees
+    Rotate(Float),
+    /// Scales around the center by a factor in [1 - amount, 1 + amount], where the amount is in [0, 1)
+    Scale(Float),
+    /// Displaces every pixel by a random field, smoothed with a gaussian of standard deviation sigma and scaled by alpha
+    Elastic { alpha: Float, sigma: Float },
+    /// Adds gaussian noise with the standard deviation
+    Noise(Float),
+    /// Erases a random rectangle of 2% to 25% of the image with the probability
+    Erase(Float),
+}
+
+impl FromStr for Augmentation {
+    type Err = ();
+
+    /// Parses an augmentation as [name]:[parameters], e.g. `rotate:10` or `elastic:8:3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, parameters) = s.split_once(':').unwrap_or((s, ""));
+        let parameters = parameters
+            .split(':')
+            .map(|x| x.parse::<Float>().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match (name, parameters.as_slice()) {
+            ("shift", [pixels]) => Ok(Self::Shift(*pixels)),
+            ("rotate", [degrees]) => Ok(Self::Rotate(*degrees)),
+            // A scale factor must stay positive
+            ("scale", [amount]) if (0.0..1.0).contains(amount) => Ok(Self::Scale(*amount)),
+            ("elastic", [alpha, sigma]) => Ok(Self::Elastic {
+                alpha: *alpha,
+                sigma: *sigma,
+            }),
+            ("noise", [std]) => Ok(Self::Noise(*std)),
+            ("erase", [probability]) => Ok(Self::Erase(*probability)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Applies augmentations to images stored row by row, with a seedable random number generator.
+/// The shift, rotation and scale are combined into a single transform, so the image is only resampled once.
+pub struct Augmenter {
+    pub augmentations: Vec<Augmentation>,
+    pub width: usize,
+    pub height: usize,
+    rng: StdRng,
+}
+
+impl Augmenter {
+    /// Creates an augmenter, which is deterministic if a seed is given.
+    pub fn new(
+        augmentations: Vec<Augmentation>,
+        width: usize,
+        height: usize,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            augmentations,
+            width,
+            height,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+        }
+    }
+
+    /// Returns a randomly augmented copy of the image
+    pub fn augment(&mut self, image: &[Float]) -> Vec<Float> {
+        let mut image = image.to_vec();
+        let augmentations = self.augmentations.clone();
+
+        let mut shift = (0.0, 0.0);
+        let mut angle: Float = 0.0;
+        let mut scale = 1.0;
+
+        for augmentation in &augmentations {
+            match *augmentation {
+                Augmentation::Shift(pixels) => {
+                    shift = (self.uniform(pixels), self.uniform(pixels));
+                }
+                Augmentation::Rotate(degrees) => angle = self.uniform(degrees).to_radians(),
+                Augmentation::Scale(amount) => scale = 1.0 + self.uniform(amount),
+                _ => {}
+            }
+        }
+
+        if shift != (0.0, 0.0) || angle != 0.0 || scale != 1.0 {
+            image = self.affine(&image, shift, angle, scale);
+        }
+
+        for augmentation in &augmentations {
+            match *augmentation {
+                Augmentation::Elastic { alpha, sigma } => {
+                    image = self.elastic(&image, alpha, sigma)
+                }
+                Augmentation::Noise(std) => {
+                    if let Ok(normal) = Normal::new(0.0, std) {
+                        for x in &mut image {
+                            *x += normal.sample(&mut self.rng);
+                        }
+                    }
+                }
+                Augmentation::Erase(probability) if self.rng.gen::<Float>() < probability => {
+                    self.erase(&mut image);
+                }
+                _ => {}
+            }
+        }
+
+        return image;
+    }
+
+    /// A random value in [-amount, amount]
+    fn uniform(&mut self, amount: Float) -> Float {
+        if amount <= 0.0 {
+            return 0.0;
+        }
+
+        return self.rng.gen_range(-amount..=amount);
+    }
+
+    /// Shifts, rotates and scales the image around its center
+    fn affine(
+        &self,
+        image: &[Float],
+        shift: (Float, Float),
+        angle: Float,
+        scale: Float,
+    ) -> Vec<Float> {
+        let center_x = (self.width as Float - 1.0) / 2.0;
+        let center_y = (self.height as Float - 1.0) / 2.0;
+        let (sin, cos) = angle.sin_cos();
+
+        return self.resample(image, |x, y| {
+            // The inverse transform maps every output pixel to its source
+            let dx = (x - center_x - shift.0) / scale;
+            let dy = (y - center_y - shift.1) / scale;
+
+            (
+                cos * dx + sin * dy + center_x,
+                -sin * dx + cos * dy + center_y,
+            )
+        });
+    }
+
+    /// Elastic distortion as described by Simard et al. (2003)
+    fn elastic(&mut self, image: &[Float], alpha: Float, sigma: Float) -> Vec<Float> {
+        let size = self.width * self.height;
+
+        let mut random_field =
+            || -> Vec<Float> { (0..size).map(|_| self.rng.gen_range(-1.0..=1.0)).collect() };
+        let (field_x, field_y) = (random_field(), random_field());
+
+        let field_x = self.blur(&field_x, sigma);
+        let field_y = self.blur(&field_y, sigma);
+
+        return self.resample(image, |x, y| {
+            let i = y as usize * self.width + x as usize;
+
+            (x + alpha * field_x[i], y + alpha * field_y[i])
+        });
+    }
+
+    /// Separable gaussian blur with the standard deviation
+    fn blur(&self, values: &[Float], sigma: Float) -> Vec<Float> {
+        if sigma <= 0.0 {
+            return values.to_vec();
+        }
+
+        let radius = (sigma * 3.0).ceil() as isize;
+        let kernel: Vec<Float> = (-radius..=radius)
+            .map(|i| (-((i * i) as Float) / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let total: Float = kernel.iter().sum();
+
+        let convolve = |values: &[Float], horizontal: bool| -> Vec<Float> {
+            (0..values.len())
+                .map(|i| {
+                    let (x, y) = ((i % self.width) as isize, (i / self.width) as isize);
+
+                    kernel
+                        .iter()
+                        .zip(-radius..=radius)
+                        .map(|(weight, offset)| {
+                            let (x, y) = if horizontal {
+                                (x + offset, y)
+                            } else {
+                                (x, y + offset)
+                            };
+
+                            if x < 0
+                                || y < 0
+                                || x >= self.width as isize
+                                || y >= self.height as isize
+                            {
+                                0.0
+                            } else {
+                                weight * values[y as usize * self.width + x as usize]
+                            }
+                        })
+                        .sum::<Float>()
+                        / total
+                })
+                .collect()
+        };
+
+        return convolve(&convolve(values, true), false);
+    }
+
+    /// Sets a random rectangle to zero
+    fn erase(&mut self, image: &mut [Float]) {
+        let area = self.rng.gen_range(0.02..=0.25) * (self.width * self.height) as Float;
+        let aspect_ratio = self
+            .rng
+            .gen_range((0.3 as Float).ln()..=(3.3 as Float).ln())
+            .exp();
+
+        let width = ((area * aspect_ratio).sqrt().round() as usize).clamp(1, self.width);
+        let height = ((area / aspect_ratio).sqrt().round() as usize).clamp(1, self.height);
+
+        let left = self.rng.gen_range(0..=self.width - width);
+        let top = self.rng.gen_range(0..=self.height - height);
+
+        for y in top..top + height {
+            for x in left..left + width {
+                image[y * self.width + x] = 0.0;
+            }
+        }
+    }
+
+    /// Creates an image by sampling the source position of every pixel with bilinear interpolation.
+    /// Positions outside the image are zero.
+    fn resample(
+        &self,
+        image: &[Float],
+        source: impl Fn(Float, Float) -> (Float, Float),
+    ) -> Vec<Float> {
+        let pixel = |x: isize, y: isize| {
+            if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+                0.0
+            } else {
+                image[y as usize * self.width + x as usize]
+            }
+        };
+
+        return (0..self.width * self.height)
+            .map(|i| {
+                let (x, y) = source((i % self.width) as Float, (i / self.width) as Float);
+                let (left, top) = (x.floor(), y.floor());
+                let (fx, fy) = (x - left, y - top);
+                let (left, top) = (left as isize, top as isize);
+
+                pixel(left, top) * (1.0 - fx) * (1.0 - fy)
+                    + pixel(left + 1, top) * fx * (1.0 - fy)
+                    + pixel(left, top + 1) * (1.0 - fx) * fy
+                    + pixel(left + 1, top + 1) * fx * fy
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Augmentation, Augmenter};
+    use neural::Float;
+
+    fn image() -> Vec<Float> {
+        (0..25).map(|i| if i == 12 { 1.0 } else { 0.0 }).collect()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("rotate:10".parse(), Ok(Augmentation::Rotate(10.0)));
+        assert_eq!(
+            "elastic:8:3".parse(),
+            Ok(Augmentation::Elastic {
+                alpha: 8.0,
+                sigma: 3.0
+            })
+        );
+
+        for augmentation in [
+            "shift",
+            "elastic:8",
+            "noise:x",
+            "blur:1",
+            "scale:1",
+            "scale:-0.1",
+        ] {
+            assert!(augmentation.parse::<Augmentation>().is_err());
+        }
+    }
+
+    #[test]
+    fn seed() {
+        let augmentations: Vec<Augmentation> = [
+            "shift:1",
+            "rotate:15",
+            "scale:0.1",
+            "elastic:2:1",
+            "noise:0.1",
+            "erase:0.5",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+        let mut a = Augmenter::new(augmentations.clone(), 5, 5, Some(1));
+        let mut b = Augmenter::new(augmentations, 5, 5, Some(1));
+
+        for _ in 0..3 {
+            assert_eq!(a.augment(&image()), b.augment(&image()));
+        }
+    }
+
+    #[test]
+    fn transforms() {
+        // Without randomness the geometric transforms are the identity
+        let mut augmenter = Augmenter::new(
+            vec![Augmentation::Shift(0.0), Augmentation::Rotate(0.0)],
+            5,
+            5,
+            Some(0),
+        );
+        assert_eq!(augmenter.augment(&image()), image());
+
+        // A quarter rotation keeps the center pixel
+        let rotated = augmenter.affine(
+            &image(),
+            (0.0, 0.0),
+            std::f64::consts::FRAC_PI_2 as Float,
+            1.0,
+        );
+        assert!((rotated[12] - 1.0).abs() < 1e-9);
+
+        let shifted = augmenter.affine(&image(), (1.0, -1.0), 0.0, 1.0);
+        assert_eq!(shifted[8], 1.0);
+        assert_eq!(shifted.iter().sum::<Float>(), 1.0);
+
+        let mut erased = vec![1.0; 25];
+        augmenter.erase(&mut erased);
+        assert!(erased.contains(&0.0));
+    }
+}
@@ -0,0 +1,413 @@
+use crate::Error;
+use neural::Float;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A column of a CSV file, selected by its header name or its index
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for Column {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(());
+        }
+
+        return Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(s.to_string()),
+        });
+    }
+}
+
+/// What to do with missing values, which are empty fields, NA, NaN or ?
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingValues {
+    /// Fails to read the file
+    Error,
+    /// Skips rows with a missing value
+    Skip,
+    /// Replaces missing features with 0
+    Zero,
+    /// Replaces missing features with the mean of their column
+    Mean,
+}
+
+impl FromStr for MissingValues {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "zero" => Ok(Self::Zero),
+            "mean" => Ok(Self::Mean),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How a CSV dataset is read
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    /// Whether the first row contains the column names, detected if None
+    pub header: Option<bool>,
+    pub delimiter: u8,
+    /// The feature columns, defaulting to all columns that aren't targets or ignored
+    pub features: Option<Vec<Column>>,
+    /// The target columns, which may be empty for inputs without targets
+    pub targets: Vec<Column>,
+    /// Columns that aren't features by default, e.g. identifiers
+    pub ignored: Vec<Column>,
+    /// Whether the single target column contains class names, which are mapped to class indices
+    pub categorical: bool,
+    /// The known class names of a categorical target in order, defaulting to the sorted distinct values
+    pub classes: Vec<String>,
+    /// Missing targets are never replaced, so their rows are skipped unless missing values are an error
+    pub missing: MissingValues,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            header: None,
+            delimiter: b',',
+            features: None,
+            targets: vec![],
+            ignored: vec![],
+            categorical: false,
+            classes: vec![],
+            missing: MissingValues::Error,
+        }
+    }
+}
+
+/// Features and targets read from a CSV file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CsvDataset {
+    pub feature_names: Vec<String>,
+    pub target_names: Vec<String>,
+    pub inputs: Vec<Vec<Float>>,
+    /// The target values of every row, or the class index for a categorical target
+    pub targets: Vec<Vec<Float>>,
+    /// The class names of a categorical target, by class index
+    pub classes: Vec<String>,
+}
+
+fn is_missing(value: &str) -> bool {
+    return matches!(value, "" | "?" | "NA" | "N/A" | "NaN" | "nan" | "null");
+}
+
+pub fn read_csv_file(path: &PathBuf, options: &CsvOptions) -> Result<CsvDataset, Error> {
+    let data = crate::io::read_file(path)?;
+
+    return parse_csv(&data, options);
+}
+
+pub fn parse_csv(data: &[u8], options: &CsvOptions) -> Result<CsvDataset, Error> {
+    let decode = |error: ::csv::Error| Error::Decode(format!("Invalid CSV: {}", error));
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(options.delimiter)
+        .trim(::csv::Trim::All)
+        .from_reader(data);
+
+    let mut rows = vec![];
+
+    for record in reader.records() {
+        let record = record.map_err(decode)?;
+
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+
+        rows.push(
+            record
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    let column_count = rows.first().map_or(0, |row| row.len());
+    let header = options.header.unwrap_or_else(|| has_header(&rows));
+    let names: Vec<String> = if header && !rows.is_empty() {
+        rows.remove(0)
+    } else {
+        (0..column_count).map(|i| i.to_string()).collect()
+    };
+
+    let index = |column: &Column| -> Result<usize, Error> {
+        let index = match column {
+            Column::Index(index) => Some(*index).filter(|index| *index < column_count),
+            Column::Name(name) => names.iter().position(|x| x == name),
+        };
+
+        return index.ok_or_else(|| Error::Decode(format!("Unknown CSV column {:?}", column)));
+    };
+
+    let targets = options
+        .targets
+        .iter()
+        .map(index)
+        .collect::<Result<Vec<_>, _>>()?;
+    let features = match &options.features {
+        Some(features) => features.iter().map(index).collect::<Result<Vec<_>, _>>()?,
+        None => {
+            let ignored = options
+                .ignored
+                .iter()
+                .map(index)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            (0..column_count)
+                .filter(|i| !targets.contains(i) && !ignored.contains(i))
+                .collect()
+        }
+    };
+
+    if options.categorical && targets.len() != 1 {
+        return Err(Error::Decode(
+            "Categorical targets should be a single column".to_string(),
+        ));
+    }
+
+    let classes = if !options.categorical {
+        vec![]
+    } else if !options.classes.is_empty() {
+        options.classes.clone()
+    } else {
+        distinct_values(rows.iter().map(|row| row[targets[0]].as_str()))
+    };
+    let class_indices: HashMap<&str, usize> = classes
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+
+    let mut dataset = CsvDataset {
+        feature_names: features.iter().map(|i| names[*i].clone()).collect(),
+        target_names: targets.iter().map(|i| names[*i].clone()).collect(),
+        classes: classes.clone(),
+        ..CsvDataset::default()
+    };
+
+    // Missing features are NaN until they're replaced
+    for (i, row) in rows.iter().enumerate() {
+        let line = i + 1 + usize::from(header);
+        let parse = |column: usize| -> Result<Option<Float>, Error> {
+            let value = &row[column];
+
+            if is_missing(value) {
+                if options.missing == MissingValues::Error {
+                    return Err(Error::Decode(format!(
+                        "Missing value in column {} on line {}",
+                        names[column], line
+                    )));
+                }
+
+                return Ok(None);
+            }
+
+            return value.parse().map(Some).map_err(|_| {
+                Error::Decode(format!(
+                    "Invalid number {:?} in column {} on line {}",
+                    value, names[column], line
+                ))
+            });
+        };
+
+        let target = if options.categorical {
+            let value = &row[targets[0]];
+
+            if is_missing(value) {
+                // Fails if missing values are an error, skips the row otherwise
+                parse(targets[0])?;
+                continue;
+            }
+
+            match class_indices.get(value.as_str()) {
+                Some(index) => vec![*index as Float],
+                None => {
+                    return Err(Error::Decode(format!(
+                        "Unknown class {:?} on line {}",
+                        value, line
+                    )))
+                }
+            }
+        } else {
+            match targets
+                .iter()
+                .map(|i| parse(*i))
+                .collect::<Result<Option<Vec<_>>, _>>()?
+            {
+                Some(target) => target,
+                None => continue,
+            }
+        };
+
+        let input = features
+            .iter()
+            .map(|i| parse(*i))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if options.missing == MissingValues::Skip && input.iter().any(|x| x.is_none()) {
+            continue;
+        }
+
+        dataset
+            .inputs
+            .push(input.into_iter().map(|x| x.unwrap_or(Float::NAN)).collect());
+        dataset.targets.push(target);
+    }
+
+    let replacements: Vec<Float> = match options.missing {
+        MissingValues::Mean => (0..features.len())
+            .map(|i| {
+                let values: Vec<Float> = dataset
+                    .inputs
+                    .iter()
+                    .map(|input| input[i])
+                    .filter(|x| !x.is_nan())
+                    .collect();
+
+                values.iter().sum::<Float>() / values.len().max(1) as Float
+            })
+            .collect(),
+        _ => vec![0.0; features.len()],
+    };
+
+    for input in &mut dataset.inputs {
+        for (x, replacement) in input.iter_mut().zip(&replacements) {
+            if x.is_nan() {
+                *x = *replacement;
+            }
+        }
+    }
+
+    return Ok(dataset);
+}
+
+/// Whether the first row is a header, which is the case if it has text in a column where the second row has a number.
+/// A single row is a header if it has any text.
+fn has_header(rows: &[Vec<String>]) -> bool {
+    let is_text = |value: &str| !is_missing(value) && value.parse::<Float>().is_err();
+
+    return match rows {
+        [] => false,
+        [first] => first.iter().any(|value| is_text(value)),
+        [first, second, ..] => first
+            .iter()
+            .zip(second)
+            .any(|(first, second)| is_text(first) && second.parse::<Float>().is_ok()),
+    };
+}
+
+/// The distinct values in ascending order, which is numeric if all values are numbers
+fn distinct_values<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut distinct: Vec<String> = values
+        .filter(|value| !is_missing(value))
+        .map(|value| value.to_string())
+        .collect();
+
+    distinct.sort();
+    distinct.dedup();
+
+    if distinct.iter().all(|value| value.parse::<Float>().is_ok()) {
+        distinct.sort_by(|a, b| {
+            a.parse::<Float>()
+                .unwrap()
+                .total_cmp(&b.parse::<Float>().unwrap())
+        });
+    }
+
+    return distinct;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_csv, Column, CsvOptions, MissingValues};
+
+    const DATA: &str = "x, y, species\n1, 2, setosa\n3, , virginica\n5, 6, setosa\n";
+
+    #[test]
+    fn columns() {
+        let options = CsvOptions {
+            targets: vec![Column::Name("species".to_string())],
+            categorical: true,
+            missing: MissingValues::Mean,
+            ..CsvOptions::default()
+        };
+
+        let dataset = parse_csv(DATA.as_bytes(), &options).unwrap();
+
+        assert_eq!(dataset.feature_names, vec!["x", "y"]);
+        assert_eq!(dataset.classes, vec!["setosa", "virginica"]);
+        assert_eq!(
+            dataset.inputs,
+            vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]
+        );
+        assert_eq!(dataset.targets, vec![vec![0.0], vec![1.0], vec![0.0]]);
+    }
+
+    #[test]
+    fn missing_values() {
+        let options = |missing| CsvOptions {
+            features: Some(vec![Column::Index(1), Column::Index(0)]),
+            missing,
+            ..CsvOptions::default()
+        };
+
+        let ignored = CsvOptions {
+            ignored: vec![Column::Name("species".to_string())],
+            missing: MissingValues::Zero,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            parse_csv(DATA.as_bytes(), &ignored).unwrap().feature_names,
+            vec!["x", "y"]
+        );
+
+        assert!(parse_csv(DATA.as_bytes(), &options(MissingValues::Error)).is_err());
+        assert_eq!(
+            parse_csv(DATA.as_bytes(), &options(MissingValues::Skip))
+                .unwrap()
+                .inputs,
+            vec![vec![2.0, 1.0], vec![6.0, 5.0]]
+        );
+        assert_eq!(
+            parse_csv(DATA.as_bytes(), &options(MissingValues::Zero))
+                .unwrap()
+                .inputs[1],
+            vec![0.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let options = CsvOptions {
+            targets: vec![Column::Index(1)],
+            categorical: true,
+            ..CsvOptions::default()
+        };
+
+        // Without a header, the columns are named by their index
+        let dataset = parse_csv(b"0.5,10\n0.1,9\n0.2,10", &options).unwrap();
+        assert_eq!(dataset.feature_names, vec!["0"]);
+        assert_eq!(dataset.classes, vec!["9", "10"]);
+        assert_eq!(dataset.targets, vec![vec![1.0], vec![0.0], vec![1.0]]);
+
+        let options = CsvOptions {
+            classes: vec!["10".to_string()],
+            ..options
+        };
+        assert!(parse_csv(b"0.5,10\n0.1,9", &options).is_err());
+    }
+}
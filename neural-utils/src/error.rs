@@ -0,0 +1,47 @@
+use std::fmt::{Display, Formatter};
+
+/// Errors returned when reading or writing files
+#[derive(Debug)]
+pub enum Error {
+    /// A file that couldn't be read or written
+    Io(std::io::Error),
+    /// A file in a format or with an extension that isn't supported
+    UnsupportedFormat(String),
+    /// A file with contents that couldn't be decoded
+    Decode(String),
+    /// A network that couldn't be decoded or is invalid
+    Network(neural::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::UnsupportedFormat(reason) => write!(f, "Unsupported format: {}", reason),
+            Self::Decode(reason) => write!(f, "Couldn't decode file: {}", reason),
+            Self::Network(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Network(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<neural::Error> for Error {
+    fn from(error: neural::Error) -> Self {
+        Self::Network(error)
+    }
+}
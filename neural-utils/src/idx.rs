@@ -1,3 +1,5 @@
+use crate::Error;
+use neural::Float;
 use byteorder::{BigEndian, ReadBytesExt};
 use std::io::{Cursor, Read};
 
@@ -6,52 +8,168 @@ pub struct IDXFile {
     pub items: Vec<Vec<u8>>,
 }
 
-pub fn parse_idx_file(data: Vec<u8>) -> Result<IDXFile, String> {
+/// An IDX file of any data type, with the values converted to floats
+pub struct IDXValues {
+    pub shape: Vec<u32>,
+    pub items: Vec<Vec<Float>>,
+}
+
+pub fn parse_idx_file(data: Vec<u8>) -> Result<IDXFile, Error> {
     let mut cursor = Cursor::new(data);
+    let (data_type, shape) = parse_header(&mut cursor)?;
 
-    // Skip magic number
-    let _ = cursor.read_u16::<BigEndian>();
+    if data_type != 0x08 {
+        return Err(Error::UnsupportedFormat(
+            "Only IDX files with unsigned byte data types are supported".to_string(),
+        ));
+    }
 
-    let data_type = cursor
-        .read_u8()
-        .or_else(|error| Err(format!("Error while decoding IDX: {}", error)))?;
+    let mut items = Vec::new();
 
-    assert_eq!(
-        data_type, 0x08,
-        "Only IDX files with unsigned byte data types are supported."
-    );
+    cursor
+        .read_to_end(&mut items)
+        .map_err(|error| Error::Decode(format!("Error while decoding IDX: {}", error)))?;
 
-    let dimensions = cursor
-        .read_u8()
-        .or_else(|error| Err(format!("Error while decoding IDX: {}", error)))?;
+    check_count(&shape, items.len())?;
 
-    let mut shape = vec![];
+    return Ok(IDXFile {
+        items: items
+            .chunks(items.len() / shape[0] as usize)
+            .map(|x| x.to_vec())
+            .collect(),
+        shape,
+    });
+}
 
-    for _ in 0..dimensions {
-        shape.push(
-            cursor
-                .read_u32::<BigEndian>()
-                .or_else(|error| Err(format!("Error while decoding IDX: {}", error)))?,
-        );
+/// Parses an IDX file of any data type, without scaling the values, e.g. for regression targets.
+pub fn parse_idx_values(data: Vec<u8>) -> Result<IDXValues, Error> {
+    let mut cursor = Cursor::new(data);
+    let (data_type, shape) = parse_header(&mut cursor)?;
+
+    let size = match data_type {
+        0x08 | 0x09 => 1,
+        0x0B => 2,
+        0x0C | 0x0D => 4,
+        0x0E => 8,
+        _ => {
+            return Err(Error::UnsupportedFormat(format!(
+                "Unknown IDX data type 0x{:02X}",
+                data_type
+            )))
+        }
+    };
+
+    let remaining = cursor.get_ref().len() - cursor.position() as usize;
+
+    if !remaining.is_multiple_of(size) {
+        return Err(Error::Decode(
+            "Error while decoding IDX: Data is not a whole number of items".to_string(),
+        ));
     }
 
-    let mut items = Vec::new();
+    check_count(&shape, remaining / size)?;
 
-    cursor
-        .read_to_end(&mut items)
-        .or_else(|error| Err(format!("Error while decoding IDX: {}", error)))?;
+    let mut values = Vec::with_capacity(remaining / size);
 
-    let expected_count = shape.iter().fold(1, |a, x| a * x) as usize;
+    for _ in 0..remaining / size {
+        let value = match data_type {
+            0x08 => cursor.read_u8().map(Float::from),
+            0x09 => cursor.read_i8().map(Float::from),
+            0x0B => cursor.read_i16::<BigEndian>().map(Float::from),
+            0x0C => cursor.read_i32::<BigEndian>().map(|x| x as Float),
+            0x0D => cursor.read_f32::<BigEndian>().map(|x| x as Float),
+            _ => cursor.read_f64::<BigEndian>().map(|x| x as Float),
+        };
 
-    if items.len() != expected_count {
-        return Err(format!("Error while decoding IDX: Expected item count ({}) is not equal to parsed item count ({})", expected_count, items.len()));
+        values.push(
+            value.map_err(|error| Error::Decode(format!("Error while decoding IDX: {}", error)))?,
+        );
     }
 
-    return Ok(IDXFile {
-        items: items
-            .chunks(items.len() / shape[0].clone() as usize)
+    return Ok(IDXValues {
+        items: values
+            .chunks(values.len() / shape[0] as usize)
             .map(|x| x.to_vec())
             .collect(),
         shape,
     });
 }
+
+/// Reads the data type and shape, skipping the magic number
+fn parse_header(cursor: &mut Cursor<Vec<u8>>) -> Result<(u8, Vec<u32>), Error> {
+    let decode_error =
+        |error: std::io::Error| Error::Decode(format!("Error while decoding IDX: {}", error));
+
+    // Skip magic number
+    let _ = cursor.read_u16::<BigEndian>();
+
+    let data_type = cursor.read_u8().map_err(decode_error)?;
+    let dimensions = cursor.read_u8().map_err(decode_error)?;
+
+    let mut shape = vec![];
+
+    for _ in 0..dimensions {
+        shape.push(cursor.read_u32::<BigEndian>().map_err(decode_error)?);
+    }
+
+    if shape.is_empty() || shape[0] == 0 {
+        return Err(Error::Decode(
+            "Error while decoding IDX: No items".to_string(),
+        ));
+    }
+
+    if shape.contains(&0) {
+        return Err(Error::Decode(
+            "Error while decoding IDX: Items are empty".to_string(),
+        ));
+    }
+
+    return Ok((data_type, shape));
+}
+
+fn check_count(shape: &[u32], count: usize) -> Result<(), Error> {
+    let expected_count = shape.iter().product::<u32>() as usize;
+
+    if count != expected_count {
+        return Err(Error::Decode(format!("Error while decoding IDX: Expected item count ({}) is not equal to parsed item count ({})", expected_count, count)));
+    }
+
+    return Ok(());
+}
+
+/// Encodes the header of an IDX file with f32 values, which should be followed by the values in big endian order
+pub fn encode_f32_header(shape: &[u32]) -> Vec<u8> {
+    let mut header = vec![0, 0, 0x0D, shape.len() as u8];
+
+    for dimension in shape {
+        header.extend(dimension.to_be_bytes());
+    }
+
+    return header;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_idx_file, parse_idx_values};
+
+    #[test]
+    fn empty_dimensions() {
+        let mut data = vec![0, 0, 0x08, 2];
+        data.extend(2u32.to_be_bytes());
+        data.extend(3u32.to_be_bytes());
+        data.extend([1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(
+            parse_idx_file(data).unwrap().items,
+            vec![vec![1, 2, 3], vec![4, 5, 6]]
+        );
+
+        // Two items without values
+        let mut data = vec![0, 0, 0x08, 2];
+        data.extend(2u32.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+
+        assert!(parse_idx_file(data.clone()).is_err());
+        assert!(parse_idx_values(data).is_err());
+    }
+}
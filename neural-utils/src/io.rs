@@ -1,21 +1,23 @@
 use crate::{
-    idx::{self, IDXFile}
+    idx::{self, IDXFile, IDXValues},
+    npy::{self, NpyArray},
+    Error,
 };
-use neural::Network;
+use neural::{Float, ModelSpec, Network};
 use std::fs;
+use std::io::ErrorKind;
 
-use bincode;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub fn read_file(path: &PathBuf) -> Result<Vec<u8>, String> {
+pub fn read_file(path: &PathBuf) -> Result<Vec<u8>, Error> {
     if !path.exists() {
-        return Err(format!("File does not exist"));
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::NotFound,
+            "File does not exist",
+        )));
     }
 
-    match fs::read(path) {
-        Err(error) => Err(format!("Couldn't read file: {}", error)),
-        Ok(data) => Ok(data),
-    }
+    return Ok(fs::read(path)?);
 }
 
 pub fn write_file(
@@ -23,43 +25,151 @@ pub fn write_file(
     path: &PathBuf,
     new: bool,
     extension: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     if new && path.exists() {
-        return Err(format!("File already exists"));
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::AlreadyExists,
+            "File already exists",
+        )));
     } else if !new && !path.exists() {
-        return Err(format!("File does not exist"));
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::NotFound,
+            "File does not exist",
+        )));
     } else if let Some(extension) = extension {
         if path.extension().is_none() || path.extension().unwrap() != extension {
-            return Err(format!("File should end with '{}' extension", extension));
+            return Err(Error::UnsupportedFormat(format!(
+                "File should end with '{}' extension",
+                extension
+            )));
         }
     }
 
-    match fs::write(path, data.as_slice()) {
-        Err(error) => Err(format!("{}", error)),
-        Ok(_) => Ok(()),
+    return Ok(fs::write(path, data.as_slice())?);
+}
+
+pub fn read_network_file(path: &PathBuf) -> Result<Network, Error> {
+    let data = read_file(path)?;
+
+    return Ok(Network::decode(data.as_slice())?);
+}
+
+pub fn read_idx_file(path: &PathBuf) -> Result<IDXFile, Error> {
+    let data = read_file(path)?;
+
+    idx::parse_idx_file(data)
+}
+
+pub fn read_idx_values(path: &PathBuf) -> Result<IDXValues, Error> {
+    let data = read_file(path)?;
+
+    idx::parse_idx_values(data)
+}
+
+/// Whether the path is an .npy file or an .npz archive, optionally followed by the name of an array, see `read_npy_array`.
+pub fn is_npy_path(path: &Path) -> bool {
+    return split_npz_path(path).is_some() || path.extension().is_some_and(|x| x == "npy");
+}
+
+/// Splits the path of an .npz archive from the name of an array after a colon, e.g. `data.npz:inputs`
+fn split_npz_path(path: &Path) -> Option<(PathBuf, Option<String>)> {
+    let path = path.to_string_lossy();
+
+    if path.ends_with(".npz") {
+        return Some((PathBuf::from(path.as_ref()), None));
     }
+
+    let (archive, name) = path.rsplit_once(':')?;
+
+    return match archive.ends_with(".npz") {
+        true => Some((PathBuf::from(archive), Some(name.to_string()))),
+        false => None,
+    };
 }
 
-pub fn read_network_file(path: &PathBuf) -> Result<Network, String> {
-    let data = match read_file(path) {
-        Err(error) => return Err(error),
-        Ok(data) => data,
+/// Reads a NumPy array from an .npy file, or from an .npz archive.
+/// The array of an archive is named after a colon, e.g. `data.npz:inputs`, defaulting to the first array.
+pub fn read_npy_array(path: &PathBuf) -> Result<NpyArray, Error> {
+    let Some((archive, name)) = split_npz_path(path) else {
+        return npy::parse_npy(&read_file(path)?);
     };
 
-    match bincode::decode_from_slice(data.as_slice(), bincode::config::standard()) {
-        Err(error) => Err(format!("Error while parsing network: {}", error)),
-        Ok((network, _)) => Ok(network),
+    let arrays = npy::parse_npz(&read_file(&archive)?)?;
+    let array = match &name {
+        Some(name) => arrays.into_iter().find(|(array_name, _)| array_name == name),
+        None => arrays.into_iter().next(),
+    };
+
+    return array.map(|(_, array)| array).ok_or_else(|| {
+        Error::Decode(format!(
+            "{} doesn't contain the array {}",
+            archive.display(),
+            name.unwrap_or_default()
+        ))
+    });
+}
+
+/// Reads float values, e.g. regression targets, from a CSV file, a NumPy array or an IDX file of any data type, based on the extension.
+/// CSV files have a row of comma separated numbers per item, with an optional header row.
+pub fn read_values_file(path: &PathBuf) -> Result<Vec<Vec<Float>>, Error> {
+    if is_npy_path(path) {
+        return Ok(read_npy_array(path)?.items());
+    }
+
+    if path.extension().is_none_or(|x| x != "csv") {
+        return Ok(read_idx_values(path)?.items);
+    }
+
+    let data = read_file(path)?;
+    let data = String::from_utf8(data).map_err(|error| Error::Decode(error.to_string()))?;
+
+    let mut items = vec![];
+
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let values = line
+            .split(',')
+            .map(|x| x.trim().parse::<Float>())
+            .collect::<Result<Vec<_>, _>>();
+
+        match values {
+            Ok(values) => items.push(values),
+            // The first row may be a header
+            Err(_) if i == 0 => continue,
+            Err(error) => {
+                return Err(Error::Decode(format!(
+                    "Invalid number on line {}: {}",
+                    i + 1,
+                    error
+                )))
+            }
+        }
     }
+
+    if items.iter().any(|item| item.len() != items[0].len()) {
+        return Err(Error::Decode(
+            "All CSV rows should have the same amount of values".to_string(),
+        ));
+    }
+
+    return Ok(items);
 }
 
-pub fn read_idx_file(path: &PathBuf) -> Result<IDXFile, String> {
-    let data = match read_file(path) {
-        Err(error) => return Err(error),
-        Ok(data) => data,
-    };
+/// Reads a model spec from a TOML or JSON file, based on the extension.
+pub fn read_model_spec(path: &PathBuf) -> Result<ModelSpec, Error> {
+    let data = read_file(path)?;
+    let data = String::from_utf8(data).map_err(|error| Error::Decode(error.to_string()))?;
 
-    match idx::parse_idx_file(data) {
-        Err(error) => Err(error),
-        Ok(idx) => Ok(idx),
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("toml") => toml::from_str(&data).map_err(|error| Error::Decode(error.to_string())),
+        Some("json") => {
+            serde_json::from_str(&data).map_err(|error| Error::Decode(error.to_string()))
+        }
+        _ => Err(Error::UnsupportedFormat(
+            "Model specs should end with '.toml' or '.json' extension".to_string(),
+        )),
     }
 }
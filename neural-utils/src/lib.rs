@@ -1,7 +1,33 @@
+#![allow(clippy::needless_return)]
+
+pub mod augmentation;
+pub mod csv;
+pub mod error;
 pub mod io;
+pub mod npy;
+pub mod preset;
 pub mod idx;
+
+pub use error::Error;
+use neural::preprocessing::{Preprocessing, Transform};
 use neural::{Network, Float};
 
+/// Converts the bytes of IDX inputs to floats.
+/// They're scaled to [0, 1] by the preprocessing of the network, see `idx_preprocessing`.
+pub fn inputs_from_bytes(items: Vec<Vec<u8>>) -> Vec<Vec<Float>> {
+    items
+        .into_iter()
+        .map(|input| input.into_iter().map(Float::from).collect())
+        .collect()
+}
+
+/// The preprocessing of IDX inputs, which scales the bytes to [0, 1]
+pub fn idx_preprocessing() -> Preprocessing {
+    Preprocessing {
+        transforms: vec![Transform::Scale(1.0 / 255.0)],
+    }
+}
+
 pub fn outputs_from_labels(network: &Network, labels: Vec<Vec<u8>>) -> Vec<Vec<Float>> {
     let labels_len = labels[0].len();
 
@@ -9,7 +35,7 @@ pub fn outputs_from_labels(network: &Network, labels: Vec<Vec<u8>>) -> Vec<Vec<F
         .into_iter()
         .map(|label| {
             return if labels_len == 1 && network.shape()[0] != 1 {
-                let mut output = vec![0.0; network.shape().last().unwrap().clone()];
+                let mut output = vec![0.0; *network.shape().last().unwrap()];
                 output[usize::from(label[0])] = 1.0;
                 output
             } else {
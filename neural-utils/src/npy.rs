@@ -0,0 +1,432 @@
+use crate::Error;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use neural::{Float, LayerEnum, Network};
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// A NumPy array of any numeric data type, with the values converted to floats in row major order
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub values: Vec<Float>,
+}
+
+impl NpyArray {
+    /// Stacks items of equal length into a 2-dimensional array
+    pub fn from_items(items: &[Vec<Float>]) -> Self {
+        let item_size = items.first().map_or(0, |item| item.len());
+
+        return Self {
+            shape: vec![items.len(), item_size],
+            values: items.concat(),
+        };
+    }
+
+    /// Splits the array along its first dimension, e.g. into the samples of a dataset.
+    /// The items of a 1-dimensional array are single values.
+    pub fn items(&self) -> Vec<Vec<Float>> {
+        let item_size = self.shape.iter().skip(1).product::<usize>();
+
+        if self.shape.is_empty() || item_size == 0 {
+            return vec![];
+        }
+
+        return self
+            .values
+            .chunks(item_size)
+            .map(|item| item.to_vec())
+            .collect();
+    }
+}
+
+fn decode_error(reason: &str) -> Error {
+    Error::Decode(format!("Invalid NPY array: {}", reason))
+}
+
+/// Parses an NPY array of booleans, integers or floats in either byte order and in C or Fortran order
+pub fn parse_npy(data: &[u8]) -> Result<NpyArray, Error> {
+    if !data.starts_with(MAGIC) || data.len() < 10 {
+        return Err(decode_error("missing magic string"));
+    }
+
+    let (header_length, header_start) = match data[6] {
+        1 => (LittleEndian::read_u16(&data[8..10]) as usize, 10),
+        2 | 3 if data.len() >= 12 => (LittleEndian::read_u32(&data[8..12]) as usize, 12),
+        version => {
+            return Err(Error::UnsupportedFormat(format!(
+                "NPY version {} isn't supported",
+                version
+            )))
+        }
+    };
+
+    let header = data
+        .get(header_start..header_start + header_length)
+        .and_then(|header| std::str::from_utf8(header).ok())
+        .ok_or_else(|| decode_error("invalid header"))?;
+    let body = &data[header_start + header_length..];
+
+    let descr = header_value(header, "descr")
+        .map(|value| value.trim_matches(|c| c == '\'' || c == '"'))
+        .ok_or_else(|| decode_error("missing descr"))?;
+    let fortran_order = header_value(header, "fortran_order") == Some("True");
+    let shape = header_value(header, "shape")
+        .map(|value| {
+            value
+                .trim_matches(|c| c == '(' || c == ')')
+                .split(',')
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.trim_end_matches('L').parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .and_then(|shape| shape.ok())
+        .ok_or_else(|| decode_error("invalid shape"))?;
+
+    let (byte_order, kind, size) = match descr.as_bytes() {
+        [order @ (b'<' | b'>' | b'|' | b'='), kind, size @ ..] => (
+            *order,
+            *kind,
+            std::str::from_utf8(size)
+                .ok()
+                .and_then(|size| size.parse::<usize>().ok())
+                .ok_or_else(|| decode_error("invalid descr"))?,
+        ),
+        _ => return Err(decode_error("invalid descr")),
+    };
+
+    if size == 0 {
+        return Err(decode_error("invalid descr"));
+    }
+
+    let length = shape
+        .iter()
+        .try_fold(size, |length, dimension| length.checked_mul(*dimension))
+        .ok_or_else(|| decode_error("shape is too large"))?;
+
+    if body.len() < length {
+        return Err(decode_error("fewer values than the shape"));
+    }
+
+    let big_endian = byte_order == b'>';
+    let value = |bytes: &[u8]| -> Option<Float> {
+        macro_rules! read {
+            ($read:ident) => {
+                if big_endian {
+                    BigEndian::$read(bytes)
+                } else {
+                    LittleEndian::$read(bytes)
+                }
+            };
+        }
+
+        return Some(match (kind, size) {
+            (b'i', 1) => bytes[0] as i8 as Float,
+            // Booleans have the kind b
+            (b'u' | b'b', 1) => bytes[0] as Float,
+            (b'i', 2) => read!(read_i16) as Float,
+            (b'u', 2) => read!(read_u16) as Float,
+            (b'i', 4) => read!(read_i32) as Float,
+            (b'u', 4) => read!(read_u32) as Float,
+            (b'i', 8) => read!(read_i64) as Float,
+            (b'u', 8) => read!(read_u64) as Float,
+            (b'f', 4) => read!(read_f32) as Float,
+            (b'f', 8) => read!(read_f64) as Float,
+            _ => return None,
+        });
+    };
+
+    let mut values = body[..length]
+        .chunks(size)
+        .map(|bytes| {
+            value(bytes).ok_or_else(|| {
+                Error::UnsupportedFormat(format!("NPY data type {} isn't supported", descr))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if fortran_order && shape.len() > 1 {
+        values = fortran_to_c_order(&values, &shape);
+    }
+
+    return Ok(NpyArray { shape, values });
+}
+
+/// Finds the value of a key in the header dictionary, e.g. `{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }`
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header
+        .find(&format!("'{}'", key))
+        .or_else(|| header.find(&format!("\"{}\"", key)))?;
+    let value = header[start + key.len() + 2..]
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start();
+
+    let end = if value.starts_with('(') {
+        value.find(')')? + 1
+    } else {
+        value.find([',', '}'])?
+    };
+
+    return Some(value[..end].trim());
+}
+
+fn fortran_to_c_order(values: &[Float], shape: &[usize]) -> Vec<Float> {
+    let mut result = vec![0.0; values.len()];
+    let mut strides = vec![1; shape.len()];
+
+    for dimension in (0..shape.len() - 1).rev() {
+        strides[dimension] = strides[dimension + 1] * shape[dimension + 1];
+    }
+
+    for (i, value) in values.iter().enumerate() {
+        // Fortran order has the first index changing fastest
+        let mut remainder = i;
+        let mut index = 0;
+
+        for (dimension, size) in shape.iter().enumerate() {
+            index += (remainder % size) * strides[dimension];
+            remainder /= size;
+        }
+
+        result[index] = *value;
+    }
+
+    return result;
+}
+
+/// Encodes an NPY version 1.0 array of little endian floats of the precision of `Float`
+pub fn encode_npy(array: &NpyArray) -> Vec<u8> {
+    let mut data = encode_npy_header(&array.shape);
+
+    for value in &array.values {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    return data;
+}
+
+/// Encodes the header of an NPY array of little endian floats of the precision of `Float`, which are followed by the values
+pub fn encode_npy_header(shape: &[usize]) -> Vec<u8> {
+    let size = std::mem::size_of::<Float>();
+    let shape = match shape {
+        [length] => format!("({},)", length),
+        shape => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f{}', 'fortran_order': False, 'shape': {}, }}",
+        size, shape
+    );
+
+    // The header is padded with spaces and ends with a newline, so the data is aligned to 64 bytes
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&[1, 0]);
+    data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    data.extend_from_slice(header.as_bytes());
+
+    return data;
+}
+
+/// Parses the arrays of an NPZ archive in archive order, named without the `.npy` extension
+pub fn parse_npz(data: &[u8]) -> Result<Vec<(String, NpyArray)>, Error> {
+    let zip_error =
+        |error: zip::result::ZipError| Error::Decode(format!("Invalid NPZ archive: {}", error));
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(zip_error)?;
+    let mut arrays = vec![];
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(zip_error)?;
+        let mut data = vec![];
+
+        file.read_to_end(&mut data)?;
+
+        let name = file.name().strip_suffix(".npy").unwrap_or(file.name());
+
+        arrays.push((name.to_string(), parse_npy(&data)?));
+    }
+
+    return Ok(arrays);
+}
+
+/// Encodes the arrays as a compressed NPZ archive, like `numpy.savez_compressed`
+pub fn encode_npz(arrays: &[(String, NpyArray)]) -> Result<Vec<u8>, Error> {
+    let zip_error = |error: zip::result::ZipError| {
+        Error::Decode(format!("Couldn't write NPZ archive: {}", error))
+    };
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, array) in arrays {
+        writer
+            .start_file(format!("{}.npy", name), options)
+            .map_err(zip_error)?;
+        writer.write_all(&encode_npy(array))?;
+    }
+
+    return Ok(writer.finish().map_err(zip_error)?.into_inner());
+}
+
+/// The weights and biases of every fully connected layer, named `layers.[index].weights` and `layers.[index].biases`.
+/// Weights have the shape (layer size, previous layer size), like `torch.nn.Linear`.
+pub fn network_weights(network: &Network) -> Vec<(String, NpyArray)> {
+    let mut arrays = vec![];
+
+    for (i, layer) in network.layers.iter().enumerate() {
+        if let LayerEnum::FullyConnected(layer) = layer {
+            arrays.push((
+                format!("layers.{}.weights", i),
+                NpyArray {
+                    shape: vec![layer.weights.nrows(), layer.weights.ncols()],
+                    values: layer.weights.transpose().as_slice().to_vec(),
+                },
+            ));
+            arrays.push((
+                format!("layers.{}.biases", i),
+                NpyArray {
+                    shape: vec![layer.biases.len()],
+                    values: layer.biases.as_slice().to_vec(),
+                },
+            ));
+        }
+    }
+
+    return arrays;
+}
+
+/// Replaces the weights and biases of fully connected layers with arrays named like `network_weights`.
+/// Returns the amount of arrays that were loaded, or an error if an array doesn't match the shape of its layer.
+pub fn load_network_weights(
+    network: &mut Network,
+    arrays: &[(String, NpyArray)],
+) -> Result<usize, Error> {
+    let mut loaded = 0;
+
+    for (name, array) in arrays {
+        let layer = name
+            .strip_prefix("layers.")
+            .and_then(|name| name.split_once('.'))
+            .and_then(|(index, parameter)| Some((index.parse::<usize>().ok()?, parameter)));
+
+        let Some((index, parameter)) = layer else {
+            continue;
+        };
+
+        let Some(LayerEnum::FullyConnected(layer)) = network.layers.get_mut(index) else {
+            return Err(Error::Decode(format!(
+                "{} doesn't belong to a fully connected layer",
+                name
+            )));
+        };
+
+        let expected_shape = match parameter {
+            "weights" => vec![layer.weights.nrows(), layer.weights.ncols()],
+            "biases" => vec![layer.biases.len()],
+            _ => continue,
+        };
+
+        if array.shape != expected_shape {
+            return Err(Error::Decode(format!(
+                "{} has the shape {:?}, but the layer has {:?}",
+                name, array.shape, expected_shape
+            )));
+        }
+
+        if parameter == "weights" {
+            let columns = layer.weights.ncols();
+
+            for (i, value) in array.values.iter().enumerate() {
+                layer.weights[(i / columns, i % columns)] = *value;
+            }
+        } else {
+            for (i, value) in array.values.iter().enumerate() {
+                layer.biases[i] = *value;
+            }
+        }
+
+        loaded += 1;
+    }
+
+    return Ok(loaded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_npy, encode_npz, parse_npy, parse_npz, NpyArray};
+
+    #[test]
+    fn round_trip() {
+        let array = NpyArray::from_items(&[vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        let data = encode_npy(&array);
+
+        assert_eq!(
+            data.len() % 64,
+            (6 * std::mem::size_of::<neural::Float>()) % 64
+        );
+        assert_eq!(parse_npy(&data).unwrap(), array);
+
+        let arrays = vec![("x".to_string(), array.clone())];
+        assert_eq!(parse_npz(&encode_npz(&arrays).unwrap()).unwrap(), arrays);
+        assert_eq!(array.items()[1], vec![4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn data_types() {
+        // numpy.array([[1, 2, 3], [4, 5, 6]], dtype='>i2', order='F')
+        let mut data = b"\x93NUMPY\x01\x00".to_vec();
+        let header = "{'descr': '>i2', 'fortran_order': True, 'shape': (2, 3), }";
+        data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        data.extend_from_slice(header.as_bytes());
+
+        for value in [1i16, 4, 2, 5, 3, 6] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let array = parse_npy(&data).unwrap();
+
+        assert_eq!(array.shape, vec![2, 3]);
+        assert_eq!(array.values, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse_npy(b"NUMPY").is_err());
+
+        let mut data = b"\x93NUMPY\x01\x00".to_vec();
+        let header = "{'descr': '<c16', 'fortran_order': False, 'shape': (1,), }";
+        data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        data.extend_from_slice(header.as_bytes());
+        data.extend_from_slice(&[0; 16]);
+
+        assert!(parse_npy(&data).is_err());
+
+        let npy = |header: &str| {
+            let mut data = b"\x93NUMPY\x01\x00".to_vec();
+            data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+            data.extend_from_slice(header.as_bytes());
+            data
+        };
+
+        assert!(parse_npy(&npy(
+            "{'descr': '<f0', 'fortran_order': False, 'shape': (1,), }"
+        ))
+        .is_err());
+        assert!(parse_npy(&npy(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (4294967296, 4294967296), }"
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{inputs_from_bytes, Error};
+use neural::{Float, Metadata};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A well known IDX image dataset, which knows how its images and labels are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    Mnist,
+    FashionMnist,
+    /// The 26 letters, with labels starting at 1
+    EmnistLetters,
+    EmnistDigits,
+    /// 47 classes, where lowercase letters that look like their uppercase letter are merged
+    EmnistBalanced,
+    /// 62 classes of digits, uppercase and lowercase letters
+    EmnistByClass,
+}
+
+const FASHION_MNIST_CLASSES: [&str; 10] = [
+    "T-shirt/top",
+    "Trouser",
+    "Pullover",
+    "Dress",
+    "Coat",
+    "Sandal",
+    "Shirt",
+    "Sneaker",
+    "Bag",
+    "Ankle boot",
+];
+
+impl Preset {
+    /// The width and height of the images
+    pub fn image_dimensions(&self) -> (usize, usize) {
+        return (28, 28);
+    }
+
+    /// Whether the images are stored column by column, which is the case for all EMNIST datasets
+    pub fn transposed(&self) -> bool {
+        return !matches!(self, Self::Mnist | Self::FashionMnist);
+    }
+
+    /// The label of the first class
+    pub fn label_offset(&self) -> u8 {
+        return match self {
+            Self::EmnistLetters => 1,
+            _ => 0,
+        };
+    }
+
+    pub fn class_names(&self) -> Vec<String> {
+        let digits = ('0'..='9').map(String::from);
+        let uppercase = ('A'..='Z').map(String::from);
+
+        return match self {
+            Self::Mnist | Self::EmnistDigits => digits.collect(),
+            Self::FashionMnist => FASHION_MNIST_CLASSES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            Self::EmnistLetters => ('a'..='z').map(String::from).collect(),
+            Self::EmnistBalanced => digits
+                .chain(uppercase)
+                .chain("abdefghnqrt".chars().map(String::from))
+                .collect(),
+            Self::EmnistByClass => digits
+                .chain(uppercase)
+                .chain(('a'..='z').map(String::from))
+                .collect(),
+        };
+    }
+
+    /// The amount of classes, which is the output size of a classifier
+    pub fn class_count(&self) -> usize {
+        return self.class_names().len();
+    }
+
+    /// Sets the preset of the metadata, and the class names and image dimensions if they aren't set yet.
+    pub fn fill_metadata(&self, metadata: &mut Metadata) {
+        metadata.preset = Some(self.to_string());
+
+        if metadata.class_names.is_empty() {
+            metadata.class_names = self.class_names();
+        }
+
+        if metadata.image_dimensions.is_none() {
+            metadata.image_dimensions = Some(self.image_dimensions());
+        }
+    }
+
+    /// Checks that the network has the input and output size of the dataset
+    pub fn check_shape(&self, shape: &[usize]) -> Result<(), String> {
+        let (width, height) = self.image_dimensions();
+
+        if shape.first() != Some(&(width * height)) {
+            return Err(format!(
+                "The {} preset has {} inputs, but the network has {}",
+                self,
+                width * height,
+                shape.first().unwrap_or(&0)
+            ));
+        }
+
+        if shape.last() != Some(&self.class_count()) {
+            return Err(format!(
+                "The {} preset has {} classes, but the network has {} outputs",
+                self,
+                self.class_count(),
+                shape.last().unwrap_or(&0)
+            ));
+        }
+
+        return Ok(());
+    }
+
+    /// Converts the images to floats and stores them row by row
+    pub fn inputs(&self, images: Vec<Vec<u8>>) -> Vec<Vec<Float>> {
+        let images = if self.transposed() {
+            let (width, height) = self.image_dimensions();
+
+            images
+                .into_iter()
+                .map(|image| {
+                    (0..width * height)
+                        .map(|i| image[(i % width) * height + i / width])
+                        .collect()
+                })
+                .collect()
+        } else {
+            images
+        };
+
+        return inputs_from_bytes(images);
+    }
+
+    /// Converts the labels to class indices starting at 0
+    pub fn labels(&self, labels: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, Error> {
+        let offset = self.label_offset();
+        let class_count = self.class_count();
+
+        return labels
+            .into_iter()
+            .map(|label| match label.as_slice() {
+                [label] if *label >= offset && usize::from(label - offset) < class_count => {
+                    Ok(vec![label - offset])
+                }
+                _ => Err(Error::Decode(format!(
+                    "{:?} isn't a label of the {} dataset",
+                    label, self
+                ))),
+            })
+            .collect();
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mnist => write!(f, "mnist"),
+            Self::FashionMnist => write!(f, "fashion-mnist"),
+            Self::EmnistLetters => write!(f, "emnist-letters"),
+            Self::EmnistDigits => write!(f, "emnist-digits"),
+            Self::EmnistBalanced => write!(f, "emnist-balanced"),
+            Self::EmnistByClass => write!(f, "emnist-byclass"),
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mnist" => Ok(Self::Mnist),
+            "fashion-mnist" => Ok(Self::FashionMnist),
+            "emnist-letters" => Ok(Self::EmnistLetters),
+            "emnist-digits" => Ok(Self::EmnistDigits),
+            "emnist-balanced" => Ok(Self::EmnistBalanced),
+            "emnist-byclass" => Ok(Self::EmnistByClass),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Preset;
+    use neural::Metadata;
+
+    #[test]
+    fn classes() {
+        assert_eq!(Preset::EmnistLetters.class_count(), 26);
+        assert_eq!(Preset::EmnistBalanced.class_count(), 47);
+        assert_eq!(Preset::EmnistByClass.class_count(), 62);
+        assert_eq!(Preset::FashionMnist.class_names()[9], "Ankle boot");
+        assert_eq!("emnist-byclass".parse(), Ok(Preset::EmnistByClass));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut metadata = Metadata {
+            class_names: vec!["x".to_string()],
+            ..Metadata::default()
+        };
+
+        Preset::EmnistLetters.fill_metadata(&mut metadata);
+
+        assert_eq!(metadata.preset.as_deref(), Some("emnist-letters"));
+        assert_eq!(metadata.class_names, vec!["x"]);
+        assert_eq!(metadata.image_dimensions, Some((28, 28)));
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(
+            Preset::EmnistLetters
+                .labels(vec![vec![1], vec![26]])
+                .unwrap(),
+            vec![vec![0], vec![25]]
+        );
+        assert!(Preset::EmnistLetters.labels(vec![vec![0]]).is_err());
+        assert!(Preset::Mnist.labels(vec![vec![10]]).is_err());
+    }
+
+    #[test]
+    fn transpose() {
+        // The second pixel of the first stored column is the first pixel of the second row
+        let mut image = vec![0; 28 * 28];
+        image[1] = 255;
+
+        assert_eq!(Preset::EmnistDigits.inputs(vec![image.clone()])[0][28], 255.0);
+        assert_eq!(Preset::Mnist.inputs(vec![image])[0][1], 255.0);
+    }
+}
@@ -1,25 +1,66 @@
 use crate::Float;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
+use nalgebra::DVector;
 use rand::Rng;
 use rand_distr::Normal;
 use serde::{Deserialize, Serialize};
 
+const SELU_ALPHA: Float = 1.673_263_242_354_377_3;
+const SELU_SCALE: Float = 1.050_700_987_355_480_5;
+
+/// sqrt(2 / pi), used in the GELU approximation
+const GELU_COEFFICIENT: Float = 0.797_884_560_802_865_4;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ActivationFunction {
+    /// Passes values through unchanged, like `Identity`
     Input,
     Sigmoid,
     ReLU,
     LeakyReLU(Float),
     Tanh,
+    /// Exponential linear unit with the given alpha
+    ELU(Float),
+    /// Scaled exponential linear unit
+    SELU,
+    /// Gaussian error linear unit, using the tanh approximation
+    GELU,
+    /// Also known as SiLU
+    Swish,
+    Softplus,
+    Mish,
+    HardSigmoid,
+    HardTanh,
+    /// Linear activation, mostly useful for regression outputs
+    Identity,
+    /// Parametric ReLU, with a learned slope per neuron.
+    /// A single slope is broadcast to the layer size when the layer is created.
+    PReLU(DVector<Float>),
+}
+
+fn sigmoid(x: Float) -> Float {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn softplus(x: Float) -> Float {
+    // Numerically stable variant of ln(1 + e^x)
+    x.max(0.0) + (1.0 + (-x.abs()).exp()).ln()
+}
+
+/// The slope of the first neuron of a PReLU, which behaves like ReLU without neurons
+fn first_slope(slopes: &DVector<Float>) -> Float {
+    slopes.iter().next().copied().unwrap_or(0.0)
 }
 
 impl ActivationFunction {
     /// Evaluate the activation function for a value.
+    /// PReLU uses the slope of its first neuron, which is the shared slope before `for_layer` broadcasts it,
+    /// use `activate` to apply the slope of every neuron.
     pub fn function(&self, x: Float) -> Float {
         match self {
-            Self::Input => panic!("Input does not have an activation function"),
-            Self::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Self::Sigmoid => sigmoid(x),
             Self::ReLU => x.max(0.0),
             Self::LeakyReLU(alpha) => {
                 if x >= 0.0 {
@@ -29,13 +70,35 @@ impl ActivationFunction {
                 }
             }
             Self::Tanh => x.tanh(),
+            Self::ELU(alpha) => {
+                if x >= 0.0 {
+                    x
+                } else {
+                    alpha * (x.exp() - 1.0)
+                }
+            }
+            Self::SELU => {
+                if x >= 0.0 {
+                    SELU_SCALE * x
+                } else {
+                    SELU_SCALE * SELU_ALPHA * (x.exp() - 1.0)
+                }
+            }
+            Self::GELU => 0.5 * x * (1.0 + (GELU_COEFFICIENT * (x + 0.044715 * x.powi(3))).tanh()),
+            Self::Swish => x * sigmoid(x),
+            Self::Softplus => softplus(x),
+            Self::Mish => x * softplus(x).tanh(),
+            Self::HardSigmoid => (x / 6.0 + 0.5).clamp(0.0, 1.0),
+            Self::HardTanh => x.clamp(-1.0, 1.0),
+            Self::Input | Self::Identity => x,
+            Self::PReLU(slopes) => Self::LeakyReLU(first_slope(slopes)).function(x),
         }
     }
 
     /// Evaluate the derivative of the activation function for a value.
+    /// PReLU uses the slope of its first neuron like `function`, use `derivatives` for every neuron.
     pub fn derivative(&self, x: Float) -> Float {
         match self {
-            Self::Input => panic!("Input does not have an activation function"),
             Self::Sigmoid => self.function(x) * (1.0 - self.function(x)),
             Self::ReLU => {
                 if x >= 0.0 {
@@ -52,25 +115,173 @@ impl ActivationFunction {
                 }
             }
             Self::Tanh => Float::from(1.0) - x.tanh().powi(2),
+            Self::ELU(alpha) => {
+                if x >= 0.0 {
+                    1.0
+                } else {
+                    alpha * x.exp()
+                }
+            }
+            Self::SELU => {
+                if x >= 0.0 {
+                    SELU_SCALE
+                } else {
+                    SELU_SCALE * SELU_ALPHA * x.exp()
+                }
+            }
+            Self::GELU => {
+                let tanh = (GELU_COEFFICIENT * (x + 0.044715 * x.powi(3))).tanh();
+
+                0.5 * (1.0 + tanh)
+                    + 0.5
+                        * x
+                        * (1.0 - tanh.powi(2))
+                        * GELU_COEFFICIENT
+                        * (1.0 + 3.0 * 0.044715 * x.powi(2))
+            }
+            Self::Swish => sigmoid(x) + x * sigmoid(x) * (1.0 - sigmoid(x)),
+            Self::Softplus => sigmoid(x),
+            Self::Mish => {
+                let tanh = softplus(x).tanh();
+
+                tanh + x * (1.0 - tanh.powi(2)) * sigmoid(x)
+            }
+            Self::HardSigmoid => {
+                if x > -3.0 && x < 3.0 {
+                    1.0 / 6.0
+                } else {
+                    0.0
+                }
+            }
+            Self::HardTanh => {
+                if x > -1.0 && x < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Input | Self::Identity => 1.0,
+            Self::PReLU(slopes) => Self::LeakyReLU(first_slope(slopes)).derivative(x),
+        }
+    }
+
+    /// Evaluate the activation function for every neuron in a layer.
+    pub fn activate(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        match self {
+            Self::PReLU(slopes) => {
+                weighted_input.zip_map(slopes, |x, slope| if x >= 0.0 { x } else { x * slope })
+            }
+            _ => weighted_input.map(|x| self.function(x)),
+        }
+    }
+
+    /// Evaluate the derivative of the activation function for every neuron in a layer.
+    pub fn derivatives(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        match self {
+            Self::PReLU(slopes) => {
+                weighted_input.zip_map(slopes, |x, slope| if x >= 0.0 { 1.0 } else { slope })
+            }
+            _ => weighted_input.map(|x| self.derivative(x)),
+        }
+    }
+
+    /// Calculate the gradient of the learnable parameters, if there are any.
+    /// The error is the partial derivative of the cost with respect to the activation.
+    pub fn parameter_gradient(
+        &self,
+        error: &DVector<Float>,
+        weighted_input: &DVector<Float>,
+    ) -> Option<DVector<Float>> {
+        match self {
+            Self::PReLU(_) => Some(error.zip_map(weighted_input, |e, x| e * x.min(0.0))),
+            _ => None,
+        }
+    }
+
+    /// Apply a (scaled) parameter gradient calculated by `parameter_gradient`.
+    pub fn apply_parameter_gradient(&mut self, gradient: &DVector<Float>) {
+        if let Self::PReLU(slopes) = self {
+            *slopes -= gradient;
+        }
+    }
+
+    /// Prepare the activation function for a layer of the given size.
+    /// Broadcasts a single PReLU slope to every neuron.
+    pub fn for_layer(self, layer_size: usize) -> Self {
+        match self {
+            Self::PReLU(slopes) if slopes.len() == 1 => {
+                Self::PReLU(DVector::from_element(layer_size, slopes[0]))
+            }
+            _ => self,
+        }
+    }
+
+    /// The amount of learnable parameters of the activation function.
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            Self::PReLU(slopes) => slopes.len(),
+            _ => 0,
         }
     }
 
     /// Initialize a weight based on the activation function.
     pub fn initialize_weight(&self, previous_layer_size: usize, rng: &mut impl Rng) -> Float {
         match self {
-            Self::Input => panic!("Input does not have weights"),
-            Self::Sigmoid | Self::Tanh => {
+            Self::Input
+            | Self::Sigmoid
+            | Self::Tanh
+            | Self::HardSigmoid
+            | Self::HardTanh
+            | Self::Identity => {
                 let bound = 1.0 / (previous_layer_size as Float).sqrt();
 
                 return rng.gen_range((-bound)..(bound));
             }
-            Self::ReLU | Self::LeakyReLU(_) => {
+            Self::ReLU
+            | Self::LeakyReLU(_)
+            | Self::PReLU(_)
+            | Self::ELU(_)
+            | Self::GELU
+            | Self::Swish
+            | Self::Softplus
+            | Self::Mish => {
                 let deviation = (2.0 / previous_layer_size as Float).sqrt();
                 let normal =
                     Normal::new(0.0, deviation).expect("Couldn't create normal distribution");
 
                 return rng.sample(normal);
             }
+            Self::SELU => {
+                // LeCun normal initialization, required for self-normalization
+                let deviation = (1.0 / previous_layer_size as Float).sqrt();
+                let normal =
+                    Normal::new(0.0, deviation).expect("Couldn't create normal distribution");
+
+                return rng.sample(normal);
+            }
+        }
+    }
+}
+
+impl Display for ActivationFunction {
+    /// Writes the name as accepted by `FromStr`, e.g. `leakyrelu(0.01)`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input => write!(f, "input"),
+            Self::Sigmoid => write!(f, "sigmoid"),
+            Self::ReLU => write!(f, "relu"),
+            Self::LeakyReLU(alpha) => write!(f, "leakyrelu({})", alpha),
+            Self::Tanh => write!(f, "tanh"),
+            Self::ELU(alpha) => write!(f, "elu({})", alpha),
+            Self::SELU => write!(f, "selu"),
+            Self::GELU => write!(f, "gelu"),
+            Self::Swish => write!(f, "swish"),
+            Self::Softplus => write!(f, "softplus"),
+            Self::Mish => write!(f, "mish"),
+            Self::HardSigmoid => write!(f, "hardsigmoid"),
+            Self::HardTanh => write!(f, "hardtanh"),
+            Self::Identity => write!(f, "identity"),
+            Self::PReLU(_) => write!(f, "prelu"),
         }
     }
 }
@@ -78,25 +289,113 @@ impl ActivationFunction {
 impl FromStr for ActivationFunction {
     type Err = ();
 
+    /// Parses an activation function name, optionally followed by a parameter, e.g. `leakyrelu(0.01)`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("leakyrelu(") && s.ends_with(")") {
-            let alpha_start = s.find("(").expect("Couldn't find open");
-            let alpha_end = s.find(")").expect("Couldn't find end");
+        let s = s.to_lowercase();
 
-            let alpha = match s[alpha_start + 1..alpha_end].parse::<Float>() {
-                Ok(alpha) => alpha,
+        let (name, parameter) = match s.find('(') {
+            Some(start) if s.ends_with(')') => match s[start + 1..s.len() - 1].parse::<Float>() {
+                Ok(parameter) => (&s[..start], Some(parameter)),
                 Err(_) => return Err(()),
-            };
+            },
+            _ => (s.as_str(), None),
+        };
 
-            return Ok(Self::LeakyReLU(alpha));
+        match (name, parameter) {
+            ("input", None) => Ok(Self::Input),
+            ("sigmoid", None) => Ok(Self::Sigmoid),
+            ("relu", None) => Ok(Self::ReLU),
+            ("leakyrelu", Some(alpha)) => Ok(Self::LeakyReLU(alpha)),
+            ("tanh", None) => Ok(Self::Tanh),
+            ("elu", alpha) => Ok(Self::ELU(alpha.unwrap_or(1.0))),
+            ("selu", None) => Ok(Self::SELU),
+            ("gelu", None) => Ok(Self::GELU),
+            ("swish" | "silu", None) => Ok(Self::Swish),
+            ("softplus", None) => Ok(Self::Softplus),
+            ("mish", None) => Ok(Self::Mish),
+            ("hardsigmoid", None) => Ok(Self::HardSigmoid),
+            ("hardtanh", None) => Ok(Self::HardTanh),
+            ("identity" | "linear", None) => Ok(Self::Identity),
+            ("prelu", slope) => Ok(Self::PReLU(DVector::from_element(1, slope.unwrap_or(0.25)))),
+            _ => Err(()),
         }
+    }
+}
 
-        match s.to_lowercase().as_str() {
-            "input" => Ok(Self::Input),
-            "sigmoid" => Ok(Self::Sigmoid),
-            "relu" => Ok(Self::ReLU),
-            "tanh" => Ok(Self::Tanh),
-            _ => Err(()),
+#[cfg(test)]
+mod tests {
+    use super::{ActivationFunction, Float};
+    use nalgebra::DVector;
+
+    #[test]
+    fn derivatives() {
+        let functions = [
+            "sigmoid",
+            "tanh",
+            "leakyrelu(0.1)",
+            "elu",
+            "selu",
+            "gelu",
+            "swish",
+            "softplus",
+            "mish",
+            "hardsigmoid",
+            "hardtanh",
+            "identity",
+        ];
+
+        let epsilon = 1e-6;
+
+        for name in functions {
+            let function: ActivationFunction = name.parse().unwrap();
+
+            for x in [-2.5, -0.7, 0.3, 1.9] {
+                let numerical = (function.function(x + epsilon) - function.function(x - epsilon))
+                    / (2.0 * epsilon);
+
+                assert!(
+                    (function.derivative(x) - numerical).abs() < 1e-4,
+                    "Incorrect derivative for {} at {}",
+                    name,
+                    x
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prelu() {
+        let function: ActivationFunction = "prelu(0.5)".parse().unwrap();
+
+        assert_eq!(function.function(-2.0), -1.0);
+        assert_eq!(function.derivative(-2.0), 0.5);
+
+        let mut function = function.for_layer(2);
+
+        let weighted_input = DVector::<Float>::from_vec(vec![-2.0, 3.0]);
+
+        assert_eq!(
+            function.activate(&weighted_input).data.as_vec().clone(),
+            vec![-1.0, 3.0]
+        );
+        assert_eq!(
+            function.derivatives(&weighted_input).data.as_vec().clone(),
+            vec![0.5, 1.0]
+        );
+
+        let gradient = function
+            .parameter_gradient(&DVector::from_vec(vec![1.0, 1.0]), &weighted_input)
+            .unwrap();
+
+        assert_eq!(gradient.data.as_vec().clone(), vec![-2.0, 0.0]);
+
+        function.apply_parameter_gradient(&(gradient * 0.1));
+
+        match function {
+            ActivationFunction::PReLU(slopes) => {
+                assert!((slopes - DVector::from_vec(vec![0.7, 0.5])).abs().max() < 1e-12)
+            }
+            _ => unreachable!(),
         }
     }
 }
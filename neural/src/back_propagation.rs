@@ -1,14 +1,46 @@
 use crate::{Float, Network, Layer};
 
-use crate::layer::BackpropagationResult;
+use crate::layer::{BackpropagationResult, GradientUpdate};
+use crate::regularization::{GradientClipping, Regularization};
 use nalgebra::DVector;
 use rand::seq::SliceRandom;
+use std::collections::HashMap;
 
 // #[cfg(feature = "threads")]
 // use crossbeam_utils::{thread};
 
+/// Options for training a network with `Network::stochastic_gradient_descent_with_options`
+#[derive(Clone, Debug)]
+pub struct TrainingOptions {
+    pub learning_rate: Float,
+    /// Weight penalties for every layer without an entry in `layer_regularization`
+    pub regularization: Regularization,
+    /// Weight penalties per layer index
+    pub layer_regularization: HashMap<usize, Regularization>,
+    pub clipping: Option<GradientClipping>,
+}
+
+impl TrainingOptions {
+    pub fn new(learning_rate: Float) -> Self {
+        Self {
+            learning_rate,
+            regularization: Regularization::default(),
+            layer_regularization: HashMap::new(),
+            clipping: None,
+        }
+    }
+
+    /// The weight penalties of the layer at the given index
+    pub fn regularization_for(&self, layer: usize) -> Regularization {
+        self.layer_regularization
+            .get(&layer)
+            .copied()
+            .unwrap_or(self.regularization)
+    }
+}
+
 impl Network {
-    /// Train the network using stochastic gradient descent
+    /// Train the network using stochastic gradient descent, returning the average loss of the batches
     ///
     /// *Single threaded*
     pub fn stochastic_gradient_descent(
@@ -16,7 +48,24 @@ impl Network {
         training_data: Vec<(Vec<Float>, Vec<Float>)>,
         batch_size: usize,
         learning_rate: Float,
-    ) {
+    ) -> Float {
+        return self.stochastic_gradient_descent_with_options(
+            training_data,
+            batch_size,
+            &TrainingOptions::new(learning_rate),
+        );
+    }
+
+    /// Train the network using stochastic gradient descent, with weight regularization and gradient clipping.
+    /// Returns the average loss of the batches, see `train_batch`.
+    ///
+    /// *Single threaded*
+    pub fn stochastic_gradient_descent_with_options(
+        &mut self,
+        training_data: Vec<(Vec<Float>, Vec<Float>)>,
+        batch_size: usize,
+        options: &TrainingOptions,
+    ) -> Float {
         let mut training_data: Vec<(DVector<Float>, DVector<Float>)> = training_data
             .into_iter()
             .map(|(input, output)| (input.into(), output.into()))
@@ -26,14 +75,85 @@ impl Network {
 
         training_data.shuffle(&mut rng);
 
-        for batch in training_data.chunks(batch_size) {
-            let results = self.train_sgd_batch(batch);
+        let batches = training_data.chunks(batch_size);
+        let batch_count = batches.len();
+        let total_loss: Float = batches.map(|batch| self.train_batch(batch, options)).sum();
+
+        return total_loss / batch_count.max(1) as Float;
+    }
 
-            self.layers
-                .iter_mut()
-                .filter(|l| l.trainable())
-                .zip(results.into_iter())
-                .for_each(|(l, r)| l.apply_results(r, learning_rate));
+    /// Train the network on a single batch, returning the loss of the batch before training.
+    /// The loss is the average cost of the samples, plus the weight penalty of the regularization.
+    pub fn train_batch(
+        &mut self,
+        batch: &[(DVector<Float>, DVector<Float>)],
+        options: &TrainingOptions,
+    ) -> Float {
+        let (results, cost) = self.train_sgd_batch(batch);
+        let loss = cost / batch.len() as Float + self.regularization_loss(options);
+
+        self.apply_batch_results(results, options);
+
+        return loss;
+    }
+
+    /// The weight penalty of all layers, which is added to the cost when training with regularization.
+    pub fn regularization_loss(&self, options: &TrainingOptions) -> Float {
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(i, l)| options.regularization_for(i).loss(&l.weight_values()))
+            .sum()
+    }
+
+    /// Apply the back propagation results of a batch to the trainable layers.
+    fn apply_batch_results(
+        &mut self,
+        results: Vec<Vec<Box<dyn BackpropagationResult>>>,
+        options: &TrainingOptions,
+    ) {
+        let trainable: Vec<usize> = (0..self.layers.len())
+            .filter(|i| self.layers[*i].trainable())
+            .collect();
+
+        let scale = match options.clipping {
+            Some(GradientClipping::Norm(max_norm)) => {
+                let norm = trainable
+                    .iter()
+                    .zip(&results)
+                    .map(|(i, results)| {
+                        let results: Vec<&dyn BackpropagationResult> =
+                            results.iter().map(|r| r.as_ref()).collect();
+
+                        self.layers[*i]
+                            .gradient_norm_squared(&results, &options.regularization_for(*i))
+                    })
+                    .sum::<Float>()
+                    .sqrt();
+
+                if norm > max_norm {
+                    max_norm / norm
+                } else {
+                    1.0
+                }
+            }
+            _ => 1.0,
+        };
+
+        let clip_value = match options.clipping {
+            Some(GradientClipping::Value(value)) => Some(value),
+            _ => None,
+        };
+
+        for (i, results) in trainable.into_iter().zip(results) {
+            let update = GradientUpdate {
+                learning_rate: options.learning_rate,
+                regularization: options.regularization_for(i),
+                scale,
+                clip_value,
+            };
+
+            self.layers[i].apply_results(results, &update);
         }
     }
 
@@ -125,19 +245,22 @@ impl Network {
         // }
     }
 
-    /// Calculate the weight and bias gradients for a specific SGD batch
+    /// Calculate the weight and bias gradients for a specific SGD batch, and the total cost of the batch
     fn train_sgd_batch(
         &self,
         batch: &[(DVector<Float>, DVector<Float>)],
-    ) -> Vec<Vec<Box<dyn BackpropagationResult>>> {
+    ) -> (Vec<Vec<Box<dyn BackpropagationResult>>>, Float) {
         // TODO: Somehow allow addition of dyn BackpropagationResult to remove nested Vec need.
         let mut results: Vec<Vec<Box<dyn BackpropagationResult>>> = vec![];
+        let mut cost = 0.0;
 
         let mut first = true;
 
         // Calculate the weight and bias gradients for a specific training sample
         for (input, expected_output) in batch {
-            let batch_results = self.back_propagate(input, expected_output);
+            let (batch_results, sample_cost) = self.back_propagate(input, expected_output);
+
+            cost += sample_cost;
 
             // On first run, gradient = 0 + delta = delta
             if first {
@@ -152,30 +275,32 @@ impl Network {
             }
         }
 
-        return results;
+        return (results, cost);
     }
 
+    /// Back propagate a single sample, returning the results of the trainable layers and the cost of the sample
     fn back_propagate(
         &self,
         input: &DVector<Float>,
         expected_output: &DVector<Float>,
-    ) -> Vec<Box<dyn BackpropagationResult>> {
+    ) -> (Vec<Box<dyn BackpropagationResult>>, Float) {
         let mut results: Vec<Box<dyn BackpropagationResult>> = vec![];
 
         let mut activations: Vec<DVector<Float>> = vec![input.clone()];
         let mut weighted_inputs: Vec<DVector<Float>> = vec![input.clone()];
 
         for layer in self.layers.iter().skip(1) {
-            let weighted_input = layer.weighted_input(&activations.last().expect("No activations"));
+            let weighted_input = layer.weighted_input(activations.last().expect("No activations"));
 
             activations.push(layer.activation(&weighted_input));
             weighted_inputs.push(weighted_input);
         }
 
+        let output = activations.last().expect("No activations");
+        let cost = self.cost_function.function(output, expected_output).sum();
+
         // Intermediate error value passed between layers
-        let mut next_error: DVector<Float> = self
-            .cost_function
-            .derivative(activations.last().expect("No activations"), expected_output);
+        let mut next_error: DVector<Float> = self.cost_function.derivative(output, expected_output);
 
         // Calculate the errors per layer from the last hidden layer to the first
         for (i, layer) in self.layers.iter().enumerate().rev() {
@@ -194,6 +319,6 @@ impl Network {
             }
         }
 
-        return results;
+        return (results, cost);
     }
 }
@@ -0,0 +1,401 @@
+use crate::layer::{
+    Branch, Conv1D, ConvTranspose2D, ConvolutionOptions, Flatten, FullyConnected, GlobalPool,
+    Input, MergeType, Pool1D, Pool2D, PoolType, Reshape, Shape, Upsample2D, UpsampleType,
+};
+use crate::{
+    ActivationFunction, CostFunction, Error, Initializer, Layer, LayerEnum, Metadata, Network,
+};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Declarative description of a network, e.g. loaded from a TOML or JSON file.
+/// Activation functions, pool types, cost functions and initializers use the same names as their `FromStr` implementations.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub cost_function: String,
+    /// Default weight initializer for all layers with weights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initializer: Option<String>,
+    /// Default bias initializer for all layers with weights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bias_initializer: Option<String>,
+    /// Seed for the random initialization, for reproducible networks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Description, class names and input information stored with the network
+    #[serde(default)]
+    pub metadata: Metadata,
+    pub layers: Vec<LayerSpec>,
+}
+
+/// Declarative description of a layer.
+/// Input dimensions are inferred from the previous layer, and 2-dimensional kernels are square.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LayerSpec {
+    Input {
+        size: usize,
+    },
+    #[serde(alias = "fc")]
+    FullyConnected {
+        activation: String,
+        size: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initializer: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bias_initializer: Option<String>,
+    },
+    Conv1d {
+        activation: String,
+        /// Splits a flat previous layer into channels. Defaults to the channels of the previous layer.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        input_channels: Option<usize>,
+        output_channels: usize,
+        kernel_size: usize,
+        #[serde(default, flatten)]
+        options: ConvolutionOptions,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initializer: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bias_initializer: Option<String>,
+    },
+    ConvTranspose2d {
+        activation: String,
+        output_channels: usize,
+        kernel_size: usize,
+        #[serde(default, flatten)]
+        options: ConvolutionOptions,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initializer: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bias_initializer: Option<String>,
+    },
+    Pool1d {
+        pool_type: String,
+        kernel_size: usize,
+        /// Defaults to the kernel size
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stride: Option<usize>,
+    },
+    Pool2d {
+        pool_type: String,
+        kernel_size: usize,
+    },
+    Upsample2d {
+        #[serde(default = "default_upsample_type")]
+        upsample_type: String,
+        scale: usize,
+    },
+    GlobalPool {
+        pool_type: String,
+    },
+    Flatten,
+    Reshape {
+        #[serde(default = "default_channels")]
+        channels: usize,
+        height: usize,
+        width: usize,
+    },
+    Branch {
+        merge_type: String,
+        branches: Vec<Vec<LayerSpec>>,
+    },
+    /// A branch adding the input to the output of the layers
+    Residual {
+        layers: Vec<LayerSpec>,
+    },
+}
+
+fn default_upsample_type() -> String {
+    "nearest".to_string()
+}
+
+fn default_channels() -> usize {
+    1
+}
+
+/// Builds a network from a model spec
+pub struct NetworkBuilder {
+    spec: ModelSpec,
+}
+
+impl NetworkBuilder {
+    pub fn new(spec: ModelSpec) -> Self {
+        Self { spec }
+    }
+
+    /// Overrides the seed of the model spec.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.spec.seed = Some(seed);
+        self
+    }
+
+    pub fn build(&self) -> Result<Network, Error> {
+        let mut rng = match self.spec.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut network = Network::new(parse::<CostFunction>(
+            "cost function",
+            &self.spec.cost_function,
+        )?);
+
+        network.metadata = self.spec.metadata.clone();
+
+        for layer in &self.spec.layers {
+            let shape = network
+                .layers
+                .last()
+                .map(|l| l.shape())
+                .unwrap_or(Shape::flat(0));
+
+            network.add_layer(self.build_layer(layer, shape, &mut rng)?)?;
+        }
+
+        return Ok(network);
+    }
+
+    /// Builds a single layer, following a layer with the given output shape.
+    fn build_layer(
+        &self,
+        spec: &LayerSpec,
+        shape: Shape,
+        rng: &mut StdRng,
+    ) -> Result<LayerEnum, Error> {
+        let layer = match spec {
+            LayerSpec::Input { size } => Input::new(*size).erased(),
+            LayerSpec::FullyConnected {
+                activation,
+                size,
+                initializer,
+                bias_initializer,
+            } => FullyConnected::with_rng(
+                shape.size(),
+                *size,
+                parse::<ActivationFunction>("activation function", activation)?,
+                self.initializer(initializer, &self.spec.initializer)?,
+                self.initializer(bias_initializer, &self.spec.bias_initializer)?,
+                rng,
+            )?
+            .erased(),
+            LayerSpec::Conv1d {
+                activation,
+                input_channels,
+                output_channels,
+                kernel_size,
+                options,
+                initializer,
+                bias_initializer,
+            } => {
+                let input_channels = input_channels.unwrap_or(shape.channels);
+
+                if input_channels == 0 || !shape.size().is_multiple_of(input_channels) {
+                    return Err(Error::InvalidParameter(format!(
+                        "Input of shape {} can't be split into {} channels",
+                        shape, input_channels
+                    )));
+                }
+
+                Conv1D::with_rng(
+                    input_channels,
+                    shape.size() / input_channels,
+                    *output_channels,
+                    *kernel_size,
+                    *options,
+                    parse("activation function", activation)?,
+                    self.initializer(initializer, &self.spec.initializer)?,
+                    self.initializer(bias_initializer, &self.spec.bias_initializer)?,
+                    rng,
+                )?
+                .erased()
+            }
+            LayerSpec::ConvTranspose2d {
+                activation,
+                output_channels,
+                kernel_size,
+                options,
+                initializer,
+                bias_initializer,
+            } => ConvTranspose2D::with_rng(
+                shape.channels,
+                shape.width,
+                shape.height,
+                *output_channels,
+                *kernel_size,
+                *kernel_size,
+                *options,
+                parse("activation function", activation)?,
+                self.initializer(initializer, &self.spec.initializer)?,
+                self.initializer(bias_initializer, &self.spec.bias_initializer)?,
+                rng,
+            )?
+            .erased(),
+            LayerSpec::Pool1d {
+                pool_type,
+                kernel_size,
+                stride,
+            } => Pool1D::inferred(
+                parse::<PoolType>("pool type", pool_type)?,
+                *kernel_size,
+                stride.unwrap_or(*kernel_size),
+            )
+            .erased(),
+            LayerSpec::Pool2d {
+                pool_type,
+                kernel_size,
+            } => Pool2D::inferred(parse("pool type", pool_type)?, *kernel_size, *kernel_size)
+                .erased(),
+            LayerSpec::Upsample2d {
+                upsample_type,
+                scale,
+            } => Upsample2D::inferred(
+                parse::<UpsampleType>("upsample type", upsample_type)?,
+                *scale,
+                *scale,
+            )
+            .erased(),
+            LayerSpec::GlobalPool { pool_type } => {
+                GlobalPool::new(parse("pool type", pool_type)?, shape).erased()
+            }
+            LayerSpec::Flatten => Flatten::new().erased(),
+            LayerSpec::Reshape {
+                channels,
+                height,
+                width,
+            } => Reshape::new(Shape::new(*channels, *height, *width)).erased(),
+            LayerSpec::Branch {
+                merge_type,
+                branches,
+            } => Branch::new(
+                parse::<MergeType>("merge type", merge_type)?,
+                shape.size(),
+                branches
+                    .iter()
+                    .map(|branch| self.build_branch(branch, shape, rng))
+                    .collect::<Result<_, _>>()?,
+            )?
+            .erased(),
+            LayerSpec::Residual { layers } => {
+                Branch::residual(shape.size(), self.build_branch(layers, shape, rng)?)?.erased()
+            }
+        };
+
+        return Ok(layer);
+    }
+
+    /// Builds the layers of a branch, connecting each to the output shape of the layer before it.
+    fn build_branch(
+        &self,
+        specs: &[LayerSpec],
+        input_shape: Shape,
+        rng: &mut StdRng,
+    ) -> Result<Vec<LayerEnum>, Error> {
+        let mut shape = input_shape;
+        let mut layers = vec![];
+
+        for spec in specs {
+            let mut layer = self.build_layer(spec, shape, rng)?;
+
+            layer.connect(shape)?;
+            shape = layer.shape();
+
+            layers.push(layer);
+        }
+
+        return Ok(layers);
+    }
+
+    /// The initializer of a layer, falling back to the default of the spec.
+    fn initializer(
+        &self,
+        layer: &Option<String>,
+        default: &Option<String>,
+    ) -> Result<Initializer, Error> {
+        match layer.as_ref().or(default.as_ref()) {
+            Some(value) => Initializer::from_spec(value),
+            None => Ok(Initializer::Auto),
+        }
+    }
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidParameter(format!("Invalid {}: {}", name, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LayerSpec, ModelSpec, NetworkBuilder};
+    use crate::layer::Shape;
+    use crate::{Error, Layer, LayerEnum};
+
+    fn spec() -> ModelSpec {
+        serde_json::from_str(
+            r#"{
+                "cost_function": "mse",
+                "initializer": "he-normal",
+                "seed": 42,
+                "metadata": { "description": "Test", "image_dimensions": [8, 8] },
+                "layers": [
+                    { "type": "input", "size": 64 },
+                    { "type": "reshape", "height": 8, "width": 8 },
+                    { "type": "pool2d", "pool_type": "max", "kernel_size": 2 },
+                    { "type": "upsample2d", "scale": 2 },
+                    { "type": "conv1d", "activation": "relu", "output_channels": 2, "kernel_size": 3, "stride": 2 },
+                    { "type": "residual", "layers": [{ "type": "fc", "activation": "tanh", "size": 62 }] },
+                    { "type": "flatten" },
+                    { "type": "fc", "activation": "sigmoid", "size": 10, "initializer": "xavier-uniform" }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn build() {
+        let network = NetworkBuilder::new(spec()).build().unwrap();
+
+        assert_eq!(network.shape(), vec![64, 64, 16, 64, 62, 62, 62, 10]);
+        assert_eq!(network.layers[3].shape(), Shape::new(1, 8, 8));
+        assert_eq!(network.layers[4].shape(), Shape::new(2, 1, 31));
+        assert_eq!(network.metadata.image_dimensions, Some((8, 8)));
+    }
+
+    #[test]
+    fn seed() {
+        let weights = |seed| match &NetworkBuilder::new(spec())
+            .seed(seed)
+            .build()
+            .unwrap()
+            .layers[7]
+        {
+            LayerEnum::FullyConnected(layer) => layer.weights.clone(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(weights(1), weights(1));
+        assert_ne!(weights(1), weights(2));
+    }
+
+    #[test]
+    fn invalid_activation() {
+        let mut spec = spec();
+
+        spec.layers[7] = LayerSpec::FullyConnected {
+            activation: "unknown".to_string(),
+            size: 10,
+            initializer: None,
+            bias_initializer: None,
+        };
+
+        assert!(matches!(
+            NetworkBuilder::new(spec).build(),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+}
@@ -1,16 +1,17 @@
 use crate::Float;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use nalgebra::DVector;
 
-#[derive(bincode::Encode, bincode::Decode)]
+#[derive(Clone, bincode::Encode, bincode::Decode)]
 pub enum CostFunction {
     MeanAbsoluteError,
     MeanSquaredError,
 }
 
 impl CostFunction {
-    // Not ever used in backpropagation, but kept here for reference
+    /// Returns the cost per output, e.g. to report the loss while training
     pub fn function(
         &self,
         output: &DVector<Float>,
@@ -43,6 +44,15 @@ impl CostFunction {
     }
 }
 
+impl Display for CostFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MeanAbsoluteError => write!(f, "mean-absolute-error"),
+            Self::MeanSquaredError => write!(f, "mean-squared-error"),
+        }
+    }
+}
+
 impl FromStr for CostFunction {
     type Err = ();
 
@@ -0,0 +1,96 @@
+use crate::layer::Shape;
+use std::fmt::{Display, Formatter};
+
+/// Errors returned by the neural network library
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The first layer of a network must be an input layer
+    MissingInputLayer,
+    /// An input layer anywhere but at the start of a network
+    UnexpectedInputLayer { layer: usize },
+    /// A layer expects a different input size than the previous layer outputs,
+    /// or the input layer (layer 0) a different size than the input
+    SizeMismatch {
+        layer: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// A layer can't infer its input dimensions from the output shape of the previous layer
+    IncompatibleShape {
+        layer: usize,
+        shape: Shape,
+        reason: String,
+    },
+    /// A layer with inconsistent parameters, e.g. from a corrupt network file
+    InvalidLayer { layer: usize, reason: String },
+    /// An invalid argument to a layer or initializer, e.g. a kernel larger than the input
+    InvalidParameter(String),
+    /// Network bytes that couldn't be decoded
+    Decode(String),
+    /// A network that couldn't be encoded
+    Encode(String),
+    /// A file that couldn't be read
+    Io(String),
+}
+
+impl Error {
+    /// The reason of an invalid parameter, or the description of any other error.
+    /// Used to attribute errors of a layer to its position in the network.
+    pub(crate) fn into_reason(self) -> String {
+        match self {
+            Self::InvalidParameter(reason) => reason,
+            error => error.to_string(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingInputLayer => write!(f, "The first layer must be an input layer"),
+            Self::UnexpectedInputLayer { layer } => {
+                write!(
+                    f,
+                    "Layer {} is an input layer, which must be the first layer",
+                    layer
+                )
+            }
+            Self::SizeMismatch {
+                layer: 0,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The input layer expects an input of size {}, but the input is of size {}",
+                expected, actual
+            ),
+            Self::SizeMismatch {
+                layer,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Layer {} expects an input of size {}, but the previous layer is of size {}",
+                layer, expected, actual
+            ),
+            Self::IncompatibleShape {
+                layer,
+                shape,
+                reason,
+            } => write!(
+                f,
+                "Layer {} can't take an input of shape {}: {}",
+                layer, shape, reason
+            ),
+            Self::InvalidLayer { layer, reason } => {
+                write!(f, "Layer {} is invalid: {}", layer, reason)
+            }
+            Self::InvalidParameter(reason) => write!(f, "Invalid parameter: {}", reason),
+            Self::Decode(reason) => write!(f, "Couldn't decode network: {}", reason),
+            Self::Encode(reason) => write!(f, "Couldn't encode network: {}", reason),
+            Self::Io(reason) => write!(f, "Couldn't read file: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
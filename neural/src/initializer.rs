@@ -0,0 +1,262 @@
+use crate::{ActivationFunction, Error, Float};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use nalgebra::{DMatrix, DVector};
+use rand::Rng;
+use rand_distr::Normal;
+use serde::{Deserialize, Serialize};
+
+/// Initialization scheme for weights and biases
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum Initializer {
+    /// Picks a scheme based on the activation function for weights, and zeros for biases.
+    #[default]
+    Auto,
+    /// Glorot uniform: U(-sqrt(6 / (fan_in + fan_out)), sqrt(6 / (fan_in + fan_out)))
+    XavierUniform,
+    /// Glorot normal: N(0, sqrt(2 / (fan_in + fan_out)))
+    XavierNormal,
+    /// Kaiming uniform: U(-sqrt(6 / fan_in), sqrt(6 / fan_in))
+    HeUniform,
+    /// Kaiming normal: N(0, sqrt(2 / fan_in))
+    HeNormal,
+    /// U(-sqrt(3 / fan_in), sqrt(3 / fan_in))
+    LeCunUniform,
+    /// N(0, sqrt(1 / fan_in))
+    LeCunNormal,
+    /// Random (semi-)orthogonal matrix multiplied by the gain
+    Orthogonal(Float),
+    Constant(Float),
+    /// Explicit values in row major order, e.g. loaded with `Initializer::from_file`
+    Values(Vec<Float>),
+}
+
+impl Initializer {
+    /// Loads explicit values from a text file containing numbers separated by whitespace or commas.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let data = fs::read_to_string(path).map_err(|error| Error::Io(error.to_string()))?;
+
+        let values = data
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse::<Float>().map_err(|_| {
+                    Error::InvalidParameter(format!("Invalid initializer value: {}", x))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return Ok(Self::Values(values));
+    }
+
+    /// Parses an initializer name, where file([path]) loads the values from a text file.
+    pub fn from_spec(value: &str) -> Result<Self, Error> {
+        if let Some(path) = value.strip_prefix("file(").and_then(|x| x.strip_suffix(')')) {
+            return Self::from_file(Path::new(path));
+        }
+
+        value
+            .parse()
+            .map_err(|_| Error::InvalidParameter(format!("Invalid initializer: {}", value)))
+    }
+
+    /// Initialize a weight matrix of shape (layer size, previous layer size).
+    pub fn weights(
+        &self,
+        layer_size: usize,
+        previous_layer_size: usize,
+        activation_function: &ActivationFunction,
+        rng: &mut impl Rng,
+    ) -> Result<DMatrix<Float>, Error> {
+        let weights = match self {
+            Self::Auto => DMatrix::from_fn(layer_size, previous_layer_size, |_, _| {
+                activation_function.initialize_weight(previous_layer_size, rng)
+            }),
+            Self::Orthogonal(gain) => orthogonal(layer_size, previous_layer_size, rng) * *gain,
+            Self::Values(values) => {
+                if values.len() != layer_size * previous_layer_size {
+                    return Err(Error::InvalidParameter(format!(
+                        "Incorrect initializer value count {}. Should be {} * {} = {}",
+                        values.len(),
+                        layer_size,
+                        previous_layer_size,
+                        layer_size * previous_layer_size
+                    )));
+                }
+
+                DMatrix::from_row_slice(layer_size, previous_layer_size, values)
+            }
+            _ => DMatrix::from_fn(layer_size, previous_layer_size, |_, _| {
+                self.sample(previous_layer_size, layer_size, rng)
+            }),
+        };
+
+        return Ok(weights);
+    }
+
+    /// Initialize a bias vector for a layer.
+    pub fn biases(
+        &self,
+        layer_size: usize,
+        previous_layer_size: usize,
+        rng: &mut impl Rng,
+    ) -> Result<DVector<Float>, Error> {
+        let biases = match self {
+            Self::Auto => DVector::zeros(layer_size),
+            Self::Orthogonal(gain) => orthogonal(layer_size, 1, rng).column(0) * *gain,
+            Self::Values(values) => {
+                if values.len() != layer_size {
+                    return Err(Error::InvalidParameter(format!(
+                        "Incorrect initializer value count {}. Should be {}",
+                        values.len(),
+                        layer_size
+                    )));
+                }
+
+                DVector::from_column_slice(values)
+            }
+            _ => DVector::from_fn(layer_size, |_, _| {
+                self.sample(previous_layer_size, layer_size, rng)
+            }),
+        };
+
+        return Ok(biases);
+    }
+
+    /// Sample a single value for the element-wise schemes.
+    fn sample(&self, fan_in: usize, fan_out: usize, rng: &mut impl Rng) -> Float {
+        let fan_in = fan_in as Float;
+        let fan_out = fan_out as Float;
+
+        match self {
+            Self::XavierUniform => uniform((6.0 / (fan_in + fan_out)).sqrt(), rng),
+            Self::XavierNormal => normal((2.0 / (fan_in + fan_out)).sqrt(), rng),
+            Self::HeUniform => uniform((6.0 / fan_in).sqrt(), rng),
+            Self::HeNormal => normal((2.0 / fan_in).sqrt(), rng),
+            Self::LeCunUniform => uniform((3.0 / fan_in).sqrt(), rng),
+            Self::LeCunNormal => normal((1.0 / fan_in).sqrt(), rng),
+            Self::Constant(value) => *value,
+            Self::Auto | Self::Orthogonal(_) | Self::Values(_) => {
+                unreachable!("Initializer is not element-wise")
+            }
+        }
+    }
+}
+
+fn uniform(bound: Float, rng: &mut impl Rng) -> Float {
+    rng.gen_range((-bound)..(bound))
+}
+
+fn normal(deviation: Float, rng: &mut impl Rng) -> Float {
+    rng.sample(Normal::new(0.0, deviation).expect("Couldn't create normal distribution"))
+}
+
+/// Generate a random matrix with orthonormal rows or columns, whichever are fewer.
+fn orthogonal(rows: usize, columns: usize, rng: &mut impl Rng) -> DMatrix<Float> {
+    let normal = Normal::new(0.0, 1.0).expect("Couldn't create normal distribution");
+
+    // QR decomposition needs at least as many rows as columns
+    let transposed = rows < columns;
+    let (m, n) = if transposed {
+        (columns, rows)
+    } else {
+        (rows, columns)
+    };
+
+    let qr = DMatrix::<Float>::from_fn(m, n, |_, _| rng.sample(normal)).qr();
+    let mut q = qr.q();
+
+    // Make the decomposition unique, so the result is uniformly distributed
+    for (i, r) in qr.r().diagonal().iter().enumerate() {
+        if *r < 0.0 {
+            q.column_mut(i).neg_mut();
+        }
+    }
+
+    return if transposed { q.transpose() } else { q };
+}
+
+impl FromStr for Initializer {
+    type Err = ();
+
+    /// Parses an initializer name, optionally followed by a parameter, e.g. `constant(0.1)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+
+        let (name, parameter) = match s.find('(') {
+            Some(start) if s.ends_with(')') => match s[start + 1..s.len() - 1].parse::<Float>() {
+                Ok(parameter) => (&s[..start], Some(parameter)),
+                Err(_) => return Err(()),
+            },
+            _ => (s.as_str(), None),
+        };
+
+        match (name, parameter) {
+            ("auto", None) => Ok(Self::Auto),
+            ("xavier-uniform" | "glorot-uniform", None) => Ok(Self::XavierUniform),
+            ("xavier-normal" | "glorot-normal", None) => Ok(Self::XavierNormal),
+            ("he-uniform" | "kaiming-uniform", None) => Ok(Self::HeUniform),
+            ("he-normal" | "kaiming-normal", None) => Ok(Self::HeNormal),
+            ("lecun-uniform", None) => Ok(Self::LeCunUniform),
+            ("lecun-normal" | "lecun", None) => Ok(Self::LeCunNormal),
+            ("orthogonal", gain) => Ok(Self::Orthogonal(gain.unwrap_or(1.0))),
+            ("constant", Some(value)) => Ok(Self::Constant(value)),
+            ("zeros", None) => Ok(Self::Constant(0.0)),
+            ("ones", None) => Ok(Self::Constant(1.0)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, Initializer};
+    use crate::ActivationFunction;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn orthogonal() {
+        let mut rng = rand::thread_rng();
+
+        for (rows, columns) in [(4, 4), (6, 3), (3, 6)] {
+            let weights = Initializer::Orthogonal(1.0)
+                .weights(rows, columns, &ActivationFunction::Tanh, &mut rng)
+                .unwrap();
+
+            let product = if rows < columns {
+                &weights * weights.transpose()
+            } else {
+                weights.transpose() * &weights
+            };
+
+            let size = rows.min(columns);
+
+            assert!(
+                (product - DMatrix::<Float>::identity(size, size))
+                    .abs()
+                    .max()
+                    < 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn values() {
+        let mut rng = rand::thread_rng();
+
+        let initializer = Initializer::Values(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let weights = initializer
+            .weights(2, 3, &ActivationFunction::ReLU, &mut rng)
+            .unwrap();
+
+        assert_eq!(
+            weights.row(0).iter().cloned().collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert!(initializer
+            .weights(3, 3, &ActivationFunction::ReLU, &mut rng)
+            .is_err());
+    }
+}
@@ -0,0 +1,435 @@
+use crate::layer::{GradientUpdate, Shape};
+use crate::regularization::Regularization;
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::str::FromStr;
+
+/// Branching layer
+/// Feeds the input through several branches of layers and merges their outputs.
+/// An empty branch passes the input through unchanged, which allows for skip (residual) connections.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Branch {
+    pub merge_type: MergeType,
+    pub input_size: usize,
+    pub branches: Vec<Vec<LayerEnum>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MergeType {
+    /// Element-wise sum of the branch outputs. All branches must have the same output size.
+    ADD,
+    /// Concatenation of the branch outputs, in branch order.
+    CONCAT,
+}
+
+impl FromStr for MergeType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" | "sum" => Ok(Self::ADD),
+            "concat" | "concatenate" => Ok(Self::CONCAT),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Branch {
+    pub fn new(
+        merge_type: MergeType,
+        input_size: usize,
+        branches: Vec<Vec<LayerEnum>>,
+    ) -> Result<Self, Error> {
+        let branch = Self {
+            merge_type,
+            input_size,
+            branches,
+        };
+
+        branch.validate()?;
+
+        return Ok(branch);
+    }
+
+    /// Creates a residual block, which adds the input to the output of the layers.
+    pub fn residual(input_size: usize, layers: Vec<LayerEnum>) -> Result<Self, Error> {
+        Self::new(MergeType::ADD, input_size, vec![vec![], layers])
+    }
+
+    /// The output size of a single branch
+    fn branch_size(&self, branch: &[LayerEnum]) -> usize {
+        branch.last().map(|l| l.size()).unwrap_or(self.input_size)
+    }
+}
+
+impl Layer for Branch {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Branch(self)
+    }
+
+    fn trainable(&self) -> bool {
+        self.branches.iter().flatten().any(|l| l.trainable())
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        self.weighted_input(input)
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        let outputs = self.branches.iter().map(|branch| {
+            branch.iter().fold(input.clone(), |activation, layer| {
+                layer.feed_forward(&activation)
+            })
+        });
+
+        match self.merge_type {
+            MergeType::ADD => outputs
+                .reduce(|a, b| a + b)
+                .expect("Branch layer has no branches"),
+            MergeType::CONCAT => {
+                DVector::from_iterator(self.size(), outputs.flat_map(|o| o.data.as_vec().clone()))
+            }
+        }
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Activation is linear, so no Hadamard product needed
+        let mut input_error = DVector::<Float>::zeros(self.input_size);
+        let mut results = vec![];
+
+        let mut offset = 0;
+
+        for branch in &self.branches {
+            let size = self.branch_size(branch);
+
+            let mut error = match self.merge_type {
+                MergeType::ADD => next_error.clone(),
+                MergeType::CONCAT => next_error.rows(offset, size).into_owned(),
+            };
+
+            offset += size;
+
+            results.push(back_propagate_branch(
+                branch,
+                &mut error,
+                previous_activation,
+            ));
+
+            input_error += error;
+        }
+
+        *next_error = input_error;
+
+        return Box::new(BranchBackpropagationResult { results });
+    }
+
+    fn apply_results(
+        &mut self,
+        results: Vec<Box<dyn BackpropagationResult>>,
+        update: &GradientUpdate,
+    ) {
+        // Regroup the results from per sample to per layer
+        let mut layer_results: Vec<Vec<Vec<Box<dyn BackpropagationResult>>>> = self
+            .branches
+            .iter()
+            .map(|b| b.iter().filter(|l| l.trainable()).map(|_| vec![]).collect())
+            .collect();
+
+        for result in results {
+            let result: Box<BranchBackpropagationResult> = match result.into_any().downcast() {
+                Ok(result) => result,
+                Err(_) => panic!("Incompatible result type for Branch layer"),
+            };
+
+            for (branch_results, new) in layer_results.iter_mut().zip(result.results) {
+                branch_results
+                    .iter_mut()
+                    .zip(new)
+                    .for_each(|(results, new)| results.push(new));
+            }
+        }
+
+        for (branch, branch_results) in self.branches.iter_mut().zip(layer_results) {
+            branch
+                .iter_mut()
+                .filter(|l| l.trainable())
+                .zip(branch_results)
+                .for_each(|(l, r)| l.apply_results(r, update));
+        }
+    }
+
+    fn gradient_norm_squared(
+        &self,
+        results: &[&dyn BackpropagationResult],
+        regularization: &Regularization,
+    ) -> Float {
+        // Regroup the results from per sample to per layer
+        let mut layer_results: Vec<Vec<Vec<&dyn BackpropagationResult>>> = self
+            .branches
+            .iter()
+            .map(|b| b.iter().filter(|l| l.trainable()).map(|_| vec![]).collect())
+            .collect();
+
+        for result in results {
+            let result: &BranchBackpropagationResult = match result.as_any().downcast_ref() {
+                Some(result) => result,
+                None => panic!("Incompatible result type for Branch layer"),
+            };
+
+            for (branch_results, new) in layer_results.iter_mut().zip(&result.results) {
+                branch_results
+                    .iter_mut()
+                    .zip(new)
+                    .for_each(|(results, new)| results.push(new.as_ref()));
+            }
+        }
+
+        return self
+            .branches
+            .iter()
+            .zip(layer_results)
+            .flat_map(|(branch, branch_results)| {
+                branch
+                    .iter()
+                    .filter(|l| l.trainable())
+                    .zip(branch_results)
+                    .map(|(l, r)| l.gradient_norm_squared(&r, regularization))
+            })
+            .sum();
+    }
+
+    fn size(&self) -> usize {
+        match self.merge_type {
+            MergeType::ADD => self.branch_size(&self.branches[0]),
+            MergeType::CONCAT => self.branches.iter().map(|b| self.branch_size(b)).sum(),
+        }
+    }
+
+    fn shape(&self) -> Shape {
+        match self.merge_type {
+            MergeType::ADD => self
+                .branches
+                .iter()
+                .find_map(|b| b.last())
+                .map(|l| l.shape())
+                .unwrap_or(Shape::flat(self.input_size)),
+            MergeType::CONCAT => Shape::flat(self.size()),
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.input_size)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        for branch in &mut self.branches {
+            let mut shape = input_shape;
+
+            for layer in branch {
+                layer.connect(shape)?;
+                shape = layer.shape();
+            }
+        }
+
+        return self.validate();
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.branches.is_empty() {
+            return Err(Error::InvalidParameter(
+                "Branch layer must have at least one branch".to_string(),
+            ));
+        }
+
+        for (i, branch) in self.branches.iter().enumerate() {
+            let mut size = self.input_size;
+
+            for (j, layer) in branch.iter().enumerate() {
+                if let Some(input_size) = layer.input_size() {
+                    if input_size != size {
+                        return Err(Error::InvalidParameter(format!(
+                            "Layer {} of branch {} expects an input of size {}, but gets {}",
+                            j, i, input_size, size
+                        )));
+                    }
+                }
+
+                layer.validate().map_err(|error| {
+                    Error::InvalidParameter(format!(
+                        "Layer {} of branch {}: {}",
+                        j,
+                        i,
+                        error.into_reason()
+                    ))
+                })?;
+
+                size = layer.size();
+            }
+        }
+
+        if let MergeType::ADD = self.merge_type {
+            let size = self.branch_size(&self.branches[0]);
+
+            if self.branches.iter().any(|b| self.branch_size(b) != size) {
+                return Err(Error::InvalidParameter(
+                    "All branches must have the same output size to be added".to_string(),
+                ));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.branches
+            .iter()
+            .flatten()
+            .map(|l| l.parameter_count())
+            .sum()
+    }
+
+    fn weight_values(&self) -> Vec<&[Float]> {
+        self.branches
+            .iter()
+            .flatten()
+            .flat_map(|l| l.weight_values())
+            .collect()
+    }
+}
+
+/// Back propagate the error through a single branch.
+/// The error is replaced by the error with respect to the branch input.
+/// Returns the results of the trainable layers in the branch.
+fn back_propagate_branch(
+    branch: &[LayerEnum],
+    error: &mut DVector<Float>,
+    input: &DVector<Float>,
+) -> Vec<Box<dyn BackpropagationResult>> {
+    let mut activations: Vec<DVector<Float>> = vec![input.clone()];
+    let mut weighted_inputs: Vec<DVector<Float>> = vec![];
+
+    for layer in branch {
+        let weighted_input = layer.weighted_input(activations.last().expect("No activations"));
+
+        activations.push(layer.activation(&weighted_input));
+        weighted_inputs.push(weighted_input);
+    }
+
+    let mut results = vec![];
+
+    for (i, layer) in branch.iter().enumerate().rev() {
+        let result = layer.back_propagate(error, &activations[i], &weighted_inputs[i]);
+
+        if layer.trainable() {
+            results.insert(0, result);
+        }
+    }
+
+    return results;
+}
+
+#[derive(Debug)]
+struct BranchBackpropagationResult {
+    /// The results of the trainable layers, per branch
+    results: Vec<Vec<Box<dyn BackpropagationResult>>>,
+}
+
+impl BackpropagationResult for BranchBackpropagationResult {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Branch, Float, Layer, LayerEnum, MergeType};
+    use crate::layer::gradient::check_weight_gradient;
+    use crate::layer::FullyConnected;
+    use crate::ActivationFunction;
+    use nalgebra::{DMatrix, DVector};
+
+    fn fully_connected(
+        weights: DMatrix<Float>,
+        activation_function: ActivationFunction,
+    ) -> FullyConnected {
+        let mut layer = FullyConnected::new(weights.ncols(), weights.nrows(), activation_function);
+        layer.weights = weights;
+
+        return layer;
+    }
+
+    #[test]
+    fn residual_feed_forward() {
+        let inner = fully_connected(
+            DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 2.0]),
+            ActivationFunction::ReLU,
+        );
+
+        let layer = Branch::residual(2, vec![inner.erased()]).unwrap();
+
+        assert_eq!(
+            layer
+                .feed_forward(&DVector::from_vec(vec![1.0, 3.0]))
+                .data
+                .as_vec()
+                .clone(),
+            vec![2.0, 9.0]
+        )
+    }
+
+    #[test]
+    fn concat_feed_forward() {
+        let inner = fully_connected(
+            DMatrix::from_row_slice(1, 2, &[1.0, 1.0]),
+            ActivationFunction::ReLU,
+        );
+
+        let layer = Branch::new(MergeType::CONCAT, 2, vec![vec![], vec![inner.erased()]]).unwrap();
+
+        assert_eq!(layer.size(), 3);
+        assert_eq!(
+            layer
+                .feed_forward(&DVector::from_vec(vec![1.0, 3.0]))
+                .data
+                .as_vec()
+                .clone(),
+            vec![1.0, 3.0, 4.0]
+        )
+    }
+
+    #[test]
+    fn residual_gradient() {
+        let layer = Branch::residual(
+            2,
+            vec![FullyConnected::new(2, 2, ActivationFunction::Tanh).erased()],
+        )
+        .unwrap();
+
+        fn inner_weights(layer: &mut LayerEnum) -> &mut DMatrix<Float> {
+            match layer {
+                LayerEnum::Branch(branch) => match &mut branch.branches[1][0] {
+                    LayerEnum::FullyConnected(layer) => &mut layer.weights,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        }
+
+        check_weight_gradient(layer, inner_weights, &[0.5, -0.3]);
+    }
+}
@@ -0,0 +1,397 @@
+use crate::layer::gradient::{
+    apply_weight_gradients, validate_weights, weight_gradient_norm_squared, WeightGradient,
+};
+use crate::layer::{GradientUpdate, Shape};
+use crate::regularization::Regularization;
+use crate::{
+    ActivationFunction, BackpropagationResult, Error, Float, Initializer, Layer, LayerEnum,
+};
+
+use nalgebra::{DMatrix, DVector};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+/// Stride, dilation and zero padding of a convolution
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvolutionOptions {
+    pub stride: usize,
+    pub dilation: usize,
+    pub padding: usize,
+}
+
+impl Default for ConvolutionOptions {
+    fn default() -> Self {
+        Self {
+            stride: 1,
+            dilation: 1,
+            padding: 0,
+        }
+    }
+}
+
+/// 1-dimensional convolution layer
+/// Input and output are expected to be channel major vectors, i.e. the values of each channel are contiguous.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Conv1D {
+    pub input_channels: usize,
+    pub input_length: usize,
+    pub output_channels: usize,
+    pub kernel_size: usize,
+    pub options: ConvolutionOptions,
+    /// Kernels of shape (output channels, input channels * kernel size)
+    pub weights: DMatrix<Float>,
+    /// A bias per output channel
+    pub biases: DVector<Float>,
+    pub activation_function: ActivationFunction,
+}
+
+impl Conv1D {
+    pub fn new(
+        input_channels: usize,
+        input_length: usize,
+        output_channels: usize,
+        kernel_size: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+    ) -> Result<Self, Error> {
+        Self::with_initializers(
+            input_channels,
+            input_length,
+            output_channels,
+            kernel_size,
+            options,
+            activation_function,
+            Initializer::Auto,
+            Initializer::Auto,
+        )
+    }
+
+    /// Create a 1-dimensional convolution layer with explicit weight and bias initializers.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_initializers(
+        input_channels: usize,
+        input_length: usize,
+        output_channels: usize,
+        kernel_size: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+    ) -> Result<Self, Error> {
+        Self::with_rng(
+            input_channels,
+            input_length,
+            output_channels,
+            kernel_size,
+            options,
+            activation_function,
+            weight_initializer,
+            bias_initializer,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a 1-dimensional convolution layer with explicit initializers, drawing random values from the given generator.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_rng(
+        input_channels: usize,
+        input_length: usize,
+        output_channels: usize,
+        kernel_size: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let mut layer = Self {
+            input_channels,
+            input_length,
+            output_channels,
+            kernel_size,
+            options,
+            weights: DMatrix::zeros(0, 0),
+            biases: DVector::zeros(0),
+            activation_function,
+        };
+
+        layer.validate_dimensions()?;
+
+        layer.weights = weight_initializer.weights(
+            output_channels,
+            input_channels * kernel_size,
+            &layer.activation_function,
+            rng,
+        )?;
+        layer.biases =
+            bias_initializer.biases(output_channels, input_channels * kernel_size, rng)?;
+        layer.activation_function = layer.activation_function.clone().for_layer(layer.size());
+
+        return Ok(layer);
+    }
+
+    /// Checks that the kernel fits in the padded input.
+    fn validate_dimensions(&self) -> Result<(), Error> {
+        if self.options.stride == 0 || self.options.dilation == 0 || self.kernel_size == 0 {
+            return Err(Error::InvalidParameter(
+                "Kernel size, stride and dilation must be at least 1".to_string(),
+            ));
+        }
+
+        if self.options.dilation * (self.kernel_size - 1)
+            >= self.input_length + 2 * self.options.padding
+        {
+            return Err(Error::InvalidParameter(
+                "Kernel doesn't fit in the padded input".to_string(),
+            ));
+        }
+
+        return Ok(());
+    }
+
+    /// The length of each output channel
+    pub fn output_length(&self) -> usize {
+        (self.input_length + 2 * self.options.padding
+            - self.options.dilation * (self.kernel_size - 1)
+            - 1)
+            / self.options.stride
+            + 1
+    }
+
+    /// The input position of a kernel element at an output position, or None if it falls in the padding.
+    fn input_position(&self, output_position: usize, kernel_position: usize) -> Option<usize> {
+        let position = (output_position * self.options.stride
+            + kernel_position * self.options.dilation) as isize
+            - self.options.padding as isize;
+
+        if position >= 0 && (position as usize) < self.input_length {
+            Some(position as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Rearranges the input into a matrix with a column of input values per output position.
+    fn columns(&self, input: &DVector<Float>) -> DMatrix<Float> {
+        DMatrix::from_fn(
+            self.input_channels * self.kernel_size,
+            self.output_length(),
+            |row, t| {
+                let (channel, k) = (row / self.kernel_size, row % self.kernel_size);
+
+                match self.input_position(t, k) {
+                    Some(position) => input[channel * self.input_length + position],
+                    None => 0.0,
+                }
+            },
+        )
+    }
+}
+
+impl Layer for Conv1D {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Conv1D(self)
+    }
+
+    fn trainable(&self) -> bool {
+        true
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        assert_eq!(
+            input.len(),
+            self.input_channels * self.input_length,
+            "Incorrect input length {}. Should be {} * {} = {}",
+            input.len(),
+            self.input_channels,
+            self.input_length,
+            self.input_channels * self.input_length
+        );
+
+        let mut output = &self.weights * self.columns(input);
+
+        for (mut row, bias) in output.row_iter_mut().zip(self.biases.iter()) {
+            row.add_scalar_mut(*bias);
+        }
+
+        // Rows are output channels, so the transpose is channel major in column major storage
+        return DVector::from_column_slice(output.transpose().as_slice());
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        self.activation_function.activate(weighted_input)
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        previous_activation: &DVector<Float>,
+        weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        let error = next_error.component_mul(&self.activation_function.derivatives(weighted_input));
+
+        // Error per output channel (rows) and output position (columns)
+        let error =
+            DMatrix::from_row_slice(self.output_channels, self.output_length(), error.as_slice());
+
+        let columns = self.columns(previous_activation);
+
+        let result = WeightGradient {
+            delta_weight_gradient: &error * columns.transpose(),
+            delta_bias_gradient: error.column_sum(),
+            delta_parameter_gradient: self
+                .activation_function
+                .parameter_gradient(next_error, weighted_input),
+        };
+
+        let column_error = self.weights.transpose() * error;
+
+        let mut input_error = DVector::<Float>::zeros(self.input_channels * self.input_length);
+
+        for t in 0..self.output_length() {
+            for row in 0..(self.input_channels * self.kernel_size) {
+                let (channel, k) = (row / self.kernel_size, row % self.kernel_size);
+
+                if let Some(position) = self.input_position(t, k) {
+                    input_error[channel * self.input_length + position] += column_error[(row, t)];
+                }
+            }
+        }
+
+        *next_error = input_error;
+
+        return Box::new(result);
+    }
+
+    fn apply_results(
+        &mut self,
+        results: Vec<Box<dyn BackpropagationResult>>,
+        update: &GradientUpdate,
+    ) {
+        apply_weight_gradients(
+            "Conv1D",
+            results,
+            update,
+            &mut self.weights,
+            &mut self.biases,
+            &mut self.activation_function,
+        );
+    }
+
+    fn gradient_norm_squared(
+        &self,
+        results: &[&dyn BackpropagationResult],
+        regularization: &Regularization,
+    ) -> Float {
+        weight_gradient_norm_squared("Conv1D", results, &self.weights, regularization)
+    }
+
+    fn size(&self) -> usize {
+        self.output_channels * self.output_length()
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(self.output_channels, 1, self.output_length())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.input_channels * self.input_length)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.validate_dimensions()?;
+
+        validate_weights(
+            &self.weights,
+            (self.output_channels, self.input_channels * self.kernel_size),
+            &self.biases,
+            self.output_channels,
+            &self.activation_function,
+            self.size(),
+        )
+    }
+
+    fn activation_function(&self) -> Option<&ActivationFunction> {
+        Some(&self.activation_function)
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.weights.len() + self.biases.len() + self.activation_function.parameter_count()
+    }
+
+    fn weight_values(&self) -> Vec<&[Float]> {
+        vec![self.weights.as_slice()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Conv1D, ConvolutionOptions, Float, Layer};
+    use crate::layer::gradient::check_weight_gradient;
+    use crate::{ActivationFunction, LayerEnum};
+    use nalgebra::{DMatrix, DVector};
+
+    #[test]
+    fn feed_forward() {
+        let mut layer = Conv1D::new(
+            1,
+            5,
+            1,
+            2,
+            ConvolutionOptions {
+                stride: 2,
+                dilation: 1,
+                padding: 1,
+            },
+            ActivationFunction::Identity,
+        )
+        .unwrap();
+
+        layer.weights = DMatrix::from_row_slice(1, 2, &[1.0, 10.0]);
+        layer.biases = DVector::from_vec(vec![0.5]);
+
+        // Padded input: 0 1 2 3 4 5 0
+        assert_eq!(layer.size(), 3);
+        assert_eq!(
+            layer
+                .feed_forward(&DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0]))
+                .data
+                .as_vec()
+                .clone(),
+            vec![10.5, 32.5, 54.5]
+        )
+    }
+
+    #[test]
+    fn gradient() {
+        let layer = Conv1D::new(
+            2,
+            6,
+            3,
+            3,
+            ConvolutionOptions {
+                stride: 1,
+                dilation: 2,
+                padding: 1,
+            },
+            ActivationFunction::Tanh,
+        )
+        .unwrap();
+
+        fn weights(layer: &mut LayerEnum) -> &mut DMatrix<Float> {
+            match layer {
+                LayerEnum::Conv1D(layer) => &mut layer.weights,
+                _ => unreachable!(),
+            }
+        }
+
+        check_weight_gradient(
+            layer,
+            weights,
+            &[
+                0.2, -0.4, 0.9, 0.1, -0.7, 0.3, 0.5, -0.2, 0.8, 0.6, -0.1, 0.4,
+            ],
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use crate::layer::gradient::{
+    apply_weight_gradients, validate_weights, weight_gradient_norm_squared, WeightGradient,
+};
+use crate::layer::{ConvolutionOptions, GradientUpdate, Shape};
+use crate::regularization::Regularization;
+use crate::{
+    ActivationFunction, BackpropagationResult, Error, Float, Initializer, Layer, LayerEnum,
+};
+
+use nalgebra::{DMatrix, DVector};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+/// 2-dimensional transposed convolution layer
+/// Input and output are expected to be channel major vectors of column major images, like `Pool2D`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConvTranspose2D {
+    pub input_channels: usize,
+    pub input_width: usize,
+    pub input_height: usize,
+    pub output_channels: usize,
+    pub kernel_width: usize,
+    pub kernel_height: usize,
+    pub options: ConvolutionOptions,
+    /// Column major kernels of shape (output channels * kernel width * kernel height, input channels)
+    pub weights: DMatrix<Float>,
+    /// A bias per output channel
+    pub biases: DVector<Float>,
+    pub activation_function: ActivationFunction,
+}
+
+impl ConvTranspose2D {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        input_channels: usize,
+        input_width: usize,
+        input_height: usize,
+        output_channels: usize,
+        kernel_width: usize,
+        kernel_height: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+    ) -> Result<Self, Error> {
+        Self::with_initializers(
+            input_channels,
+            input_width,
+            input_height,
+            output_channels,
+            kernel_width,
+            kernel_height,
+            options,
+            activation_function,
+            Initializer::Auto,
+            Initializer::Auto,
+        )
+    }
+
+    /// Create a 2-dimensional transposed convolution layer with explicit weight and bias initializers.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_initializers(
+        input_channels: usize,
+        input_width: usize,
+        input_height: usize,
+        output_channels: usize,
+        kernel_width: usize,
+        kernel_height: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+    ) -> Result<Self, Error> {
+        Self::with_rng(
+            input_channels,
+            input_width,
+            input_height,
+            output_channels,
+            kernel_width,
+            kernel_height,
+            options,
+            activation_function,
+            weight_initializer,
+            bias_initializer,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a 2-dimensional transposed convolution layer with explicit initializers, drawing random values from the given generator.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_rng(
+        input_channels: usize,
+        input_width: usize,
+        input_height: usize,
+        output_channels: usize,
+        kernel_width: usize,
+        kernel_height: usize,
+        options: ConvolutionOptions,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let mut layer = Self {
+            input_channels,
+            input_width,
+            input_height,
+            output_channels,
+            kernel_width,
+            kernel_height,
+            options,
+            weights: DMatrix::zeros(0, 0),
+            biases: DVector::zeros(0),
+            activation_function,
+        };
+
+        layer.validate_dimensions()?;
+
+        let kernel_size = output_channels * kernel_width * kernel_height;
+
+        // Every input value is spread over a whole kernel of every output channel
+        layer.weights = weight_initializer
+            .weights(input_channels, kernel_size, &layer.activation_function, rng)?
+            .transpose();
+        layer.biases = bias_initializer.biases(output_channels, kernel_size, rng)?;
+        layer.activation_function = layer.activation_function.clone().for_layer(layer.size());
+
+        return Ok(layer);
+    }
+
+    /// Checks that the padding leaves an output.
+    fn validate_dimensions(&self) -> Result<(), Error> {
+        if self.options.stride == 0
+            || self.options.dilation == 0
+            || self.kernel_width == 0
+            || self.kernel_height == 0
+            || self.input_width == 0
+            || self.input_height == 0
+        {
+            return Err(Error::InvalidParameter(
+                "Input size, kernel size, stride and dilation must be at least 1".to_string(),
+            ));
+        }
+
+        if self.output_extent(self.input_width, self.kernel_width) <= 2 * self.options.padding
+            || self.output_extent(self.input_height, self.kernel_height) <= 2 * self.options.padding
+        {
+            return Err(Error::InvalidParameter(
+                "Padding must be smaller than half the output size".to_string(),
+            ));
+        }
+
+        return Ok(());
+    }
+
+    /// The output size along a dimension before padding is removed
+    fn output_extent(&self, input_size: usize, kernel_size: usize) -> usize {
+        (input_size - 1) * self.options.stride + self.options.dilation * (kernel_size - 1) + 1
+    }
+
+    pub fn output_width(&self) -> usize {
+        self.output_extent(self.input_width, self.kernel_width) - 2 * self.options.padding
+    }
+
+    pub fn output_height(&self) -> usize {
+        self.output_extent(self.input_height, self.kernel_height) - 2 * self.options.padding
+    }
+
+    /// The output position a kernel element of an input position contributes to, or None if it falls in the padding.
+    fn output_position(
+        &self,
+        input_position: usize,
+        kernel_position: usize,
+        output_size: usize,
+    ) -> Option<usize> {
+        let position = (input_position * self.options.stride
+            + kernel_position * self.options.dilation) as isize
+            - self.options.padding as isize;
+
+        if position >= 0 && (position as usize) < output_size {
+            Some(position as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Calls f with the weight row, input pixel and output index of every contribution.
+    fn for_each_contribution(&self, mut f: impl FnMut(usize, usize, usize)) {
+        let (output_width, output_height) = (self.output_width(), self.output_height());
+
+        for x in 0..self.input_width {
+            for y in 0..self.input_height {
+                let pixel = x * self.input_height + y;
+
+                for kx in 0..self.kernel_width {
+                    let ox = match self.output_position(x, kx, output_width) {
+                        Some(ox) => ox,
+                        None => continue,
+                    };
+
+                    for ky in 0..self.kernel_height {
+                        let oy = match self.output_position(y, ky, output_height) {
+                            Some(oy) => oy,
+                            None => continue,
+                        };
+
+                        for channel in 0..self.output_channels {
+                            let row = (channel * self.kernel_width + kx) * self.kernel_height + ky;
+                            let output =
+                                channel * output_width * output_height + ox * output_height + oy;
+
+                            f(row, pixel, output);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The input as a matrix of input channels (rows) and pixels (columns)
+    fn input_matrix(&self, input: &DVector<Float>) -> DMatrix<Float> {
+        DMatrix::from_row_slice(
+            self.input_channels,
+            self.input_width * self.input_height,
+            input.as_slice(),
+        )
+    }
+}
+
+impl Layer for ConvTranspose2D {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::ConvTranspose2D(self)
+    }
+
+    fn trainable(&self) -> bool {
+        true
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        assert_eq!(
+            input.len(),
+            self.input_channels * self.input_width * self.input_height,
+            "Incorrect input length {}. Should be {} * {} * {} = {}",
+            input.len(),
+            self.input_channels,
+            self.input_width,
+            self.input_height,
+            self.input_channels * self.input_width * self.input_height
+        );
+
+        let columns = &self.weights * self.input_matrix(input);
+        let channel_size = self.output_width() * self.output_height();
+
+        let mut output = DVector::from_fn(self.size(), |i, _| self.biases[i / channel_size]);
+
+        self.for_each_contribution(|row, pixel, index| output[index] += columns[(row, pixel)]);
+
+        return output;
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        self.activation_function.activate(weighted_input)
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        previous_activation: &DVector<Float>,
+        weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        let error = next_error.component_mul(&self.activation_function.derivatives(weighted_input));
+
+        // Gather the error of every contribution, the transpose of the scatter in the forward pass
+        let mut column_error =
+            DMatrix::<Float>::zeros(self.weights.nrows(), self.input_width * self.input_height);
+
+        self.for_each_contribution(|row, pixel, index| column_error[(row, pixel)] = error[index]);
+
+        let channel_size = self.output_width() * self.output_height();
+
+        let result = WeightGradient {
+            delta_weight_gradient: &column_error
+                * self.input_matrix(previous_activation).transpose(),
+            delta_bias_gradient: DVector::from_fn(self.output_channels, |channel, _| {
+                error.rows(channel * channel_size, channel_size).sum()
+            }),
+            delta_parameter_gradient: self
+                .activation_function
+                .parameter_gradient(next_error, weighted_input),
+        };
+
+        let input_error = self.weights.transpose() * column_error;
+
+        // Input channels are rows, so the transpose is channel major in column major storage
+        *next_error = DVector::from_column_slice(input_error.transpose().as_slice());
+
+        return Box::new(result);
+    }
+
+    fn apply_results(
+        &mut self,
+        results: Vec<Box<dyn BackpropagationResult>>,
+        update: &GradientUpdate,
+    ) {
+        apply_weight_gradients(
+            "ConvTranspose2D",
+            results,
+            update,
+            &mut self.weights,
+            &mut self.biases,
+            &mut self.activation_function,
+        );
+    }
+
+    fn gradient_norm_squared(
+        &self,
+        results: &[&dyn BackpropagationResult],
+        regularization: &Regularization,
+    ) -> Float {
+        weight_gradient_norm_squared("ConvTranspose2D", results, &self.weights, regularization)
+    }
+
+    fn size(&self) -> usize {
+        self.output_channels * self.output_width() * self.output_height()
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(
+            self.output_channels,
+            self.output_height(),
+            self.output_width(),
+        )
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.input_channels * self.input_width * self.input_height)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.validate_dimensions()?;
+
+        validate_weights(
+            &self.weights,
+            (
+                self.output_channels * self.kernel_width * self.kernel_height,
+                self.input_channels,
+            ),
+            &self.biases,
+            self.output_channels,
+            &self.activation_function,
+            self.size(),
+        )
+    }
+
+    fn activation_function(&self) -> Option<&ActivationFunction> {
+        Some(&self.activation_function)
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.weights.len() + self.biases.len() + self.activation_function.parameter_count()
+    }
+
+    fn weight_values(&self) -> Vec<&[Float]> {
+        vec![self.weights.as_slice()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConvTranspose2D, ConvolutionOptions, Float, Layer};
+    use crate::layer::gradient::check_weight_gradient;
+    use crate::{ActivationFunction, LayerEnum};
+    use nalgebra::{DMatrix, DVector};
+
+    #[test]
+    fn feed_forward() {
+        let mut layer = ConvTranspose2D::new(
+            1,
+            2,
+            1,
+            1,
+            2,
+            1,
+            ConvolutionOptions {
+                stride: 2,
+                dilation: 1,
+                padding: 0,
+            },
+            ActivationFunction::Identity,
+        )
+        .unwrap();
+
+        layer.weights = DMatrix::from_column_slice(2, 1, &[1.0, 10.0]);
+
+        // Every input pixel is spread over two output pixels
+        assert_eq!((layer.output_width(), layer.output_height()), (4, 1));
+        assert_eq!(
+            layer
+                .feed_forward(&DVector::from_vec(vec![1.0, 2.0]))
+                .data
+                .as_vec()
+                .clone(),
+            vec![1.0, 10.0, 2.0, 20.0]
+        )
+    }
+
+    #[test]
+    fn gradient() {
+        let layer = ConvTranspose2D::new(
+            2,
+            2,
+            2,
+            2,
+            3,
+            2,
+            ConvolutionOptions {
+                stride: 2,
+                dilation: 1,
+                padding: 1,
+            },
+            ActivationFunction::Tanh,
+        )
+        .unwrap();
+
+        fn weights(layer: &mut LayerEnum) -> &mut DMatrix<Float> {
+            match layer {
+                LayerEnum::ConvTranspose2D(layer) => &mut layer.weights,
+                _ => unreachable!(),
+            }
+        }
+
+        check_weight_gradient(layer, weights, &[0.2, -0.4, 0.9, 0.1, -0.7, 0.3, 0.5, -0.2]);
+    }
+}
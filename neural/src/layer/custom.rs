@@ -0,0 +1,222 @@
+use crate::{Layer, LayerEnum};
+
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// A user-defined layer that can be stored in a network.
+///
+/// Custom layers are wrapped in `LayerEnum::Custom` and serialized together with their `TAG`.
+/// The layer type must be registered with `register_layer` before a network containing it can be decoded.
+pub trait CustomLayer: Layer + Clone + Serialize + DeserializeOwned + Send + 'static {
+    /// Unique tag identifying the layer type in serialized networks.
+    const TAG: &'static str;
+}
+
+/// Object safe counterpart of `CustomLayer`, implemented for every custom layer.
+pub trait ErasedCustomLayer: Layer + Send {
+    /// The tag of the layer type.
+    fn tag(&self) -> &'static str;
+
+    /// Encodes the layer state.
+    fn encode(&self) -> Result<Vec<u8>, String>;
+
+    fn box_clone(&self) -> Box<dyn ErasedCustomLayer>;
+
+    fn as_layer(&self) -> &dyn Layer;
+
+    fn as_layer_mut(&mut self) -> &mut dyn Layer;
+}
+
+impl<T: CustomLayer> ErasedCustomLayer for T {
+    fn tag(&self) -> &'static str {
+        T::TAG
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|error| format!("Couldn't encode custom layer {}: {}", T::TAG, error))
+    }
+
+    fn box_clone(&self) -> Box<dyn ErasedCustomLayer> {
+        Box::new(self.clone())
+    }
+
+    fn as_layer(&self) -> &dyn Layer {
+        self
+    }
+
+    fn as_layer_mut(&mut self) -> &mut dyn Layer {
+        self
+    }
+}
+
+impl Clone for Box<dyn ErasedCustomLayer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+type Decoder = fn(&[u8]) -> Result<Box<dyn ErasedCustomLayer>, String>;
+
+fn registry() -> &'static RwLock<HashMap<&'static str, Decoder>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<&'static str, Decoder>>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn decode<T: CustomLayer>(data: &[u8]) -> Result<Box<dyn ErasedCustomLayer>, String> {
+    match bincode::serde::decode_from_slice::<T, _>(data, bincode::config::standard()) {
+        Err(error) => Err(format!(
+            "Couldn't decode custom layer {}: {}",
+            T::TAG,
+            error
+        )),
+        Ok((layer, _)) => Ok(Box::new(layer)),
+    }
+}
+
+/// Registers a custom layer type, so networks containing it can be decoded.
+/// Registering the same tag again replaces the previous registration.
+pub fn register_layer<T: CustomLayer>() {
+    registry()
+        .write()
+        .expect("Layer registry poisoned")
+        .insert(T::TAG, decode::<T>);
+}
+
+/// Returns whether a custom layer type is registered under the tag.
+pub fn is_registered(tag: &str) -> bool {
+    registry()
+        .read()
+        .expect("Layer registry poisoned")
+        .contains_key(tag)
+}
+
+impl Serialize for Box<dyn ErasedCustomLayer> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = self.encode().map_err(serde::ser::Error::custom)?;
+
+        (self.tag(), data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn ErasedCustomLayer> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (tag, data) = <(String, Vec<u8>)>::deserialize(deserializer)?;
+
+        let decoder = match registry()
+            .read()
+            .expect("Layer registry poisoned")
+            .get(tag.as_str())
+        {
+            Some(decoder) => *decoder,
+            None => {
+                return Err(D::Error::custom(format!(
+                    "Unregistered custom layer: {}",
+                    tag
+                )))
+            }
+        };
+
+        decoder(&data).map_err(D::Error::custom)
+    }
+}
+
+impl LayerEnum {
+    /// Wraps a custom layer, for use in `Layer::erased`.
+    pub fn custom<T: CustomLayer>(layer: T) -> Self {
+        Self::Custom(Box::new(layer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_registered, register_layer, CustomLayer, ErasedCustomLayer};
+    use crate::layer::Input;
+    use crate::{BackpropagationResult, CostFunction, Float, Layer, LayerEnum, Network};
+    use nalgebra::DVector;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Scale {
+        size: usize,
+        factor: Float,
+    }
+
+    impl CustomLayer for Scale {
+        const TAG: &'static str = "test-scale";
+    }
+
+    impl Layer for Scale {
+        fn erased(self) -> LayerEnum {
+            LayerEnum::custom(self)
+        }
+
+        fn trainable(&self) -> bool {
+            false
+        }
+
+        fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+            input * self.factor
+        }
+
+        fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+            weighted_input.clone()
+        }
+
+        fn back_propagate(
+            &self,
+            next_error: &mut DVector<Float>,
+            _previous_activation: &DVector<Float>,
+            _weighted_input: &DVector<Float>,
+        ) -> Box<dyn BackpropagationResult> {
+            *next_error *= self.factor;
+
+            return Box::new(());
+        }
+
+        fn size(&self) -> usize {
+            self.size
+        }
+    }
+
+    fn network() -> Network {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(Scale {
+                size: 2,
+                factor: 3.0,
+            })
+            .unwrap();
+
+        return network;
+    }
+
+    #[test]
+    fn custom_layer_round_trip() {
+        register_layer::<Scale>();
+
+        let encoded = bincode::encode_to_vec(network(), bincode::config::standard()).unwrap();
+        let (decoded, _): (Network, _) =
+            bincode::decode_from_slice(&encoded, bincode::config::standard()).unwrap();
+
+        assert_eq!(decoded.feed_forward(vec![1.0, 2.0]), vec![3.0, 6.0]);
+    }
+
+    #[test]
+    fn unregistered_custom_layer() {
+        let config = bincode::config::standard();
+        let encoded =
+            bincode::serde::encode_to_vec(("test-unregistered", Vec::<u8>::new()), config).unwrap();
+
+        assert!(!is_registered("test-unregistered"));
+        assert!(
+            bincode::serde::decode_from_slice::<Box<dyn ErasedCustomLayer>, _>(&encoded, config)
+                .is_err()
+        );
+    }
+}
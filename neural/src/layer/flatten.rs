@@ -0,0 +1,64 @@
+use crate::layer::Shape;
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+/// Flatten layer
+/// Passes the values through unchanged as a flat vector, e.g. between image layers and a fully connected layer.
+/// The size is inferred from the previous layer.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Flatten {
+    pub size: usize,
+}
+
+impl Flatten {
+    pub fn new() -> Self {
+        Self { size: 0 }
+    }
+}
+
+impl Layer for Flatten {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Flatten(self)
+    }
+
+    fn trainable(&self) -> bool {
+        false
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        input.clone()
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        input.clone()
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        _next_error: &mut DVector<Float>,
+        _previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Values are unchanged, so the error passes through as well
+        return Box::new(());
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.size)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        self.size = input_shape.size();
+
+        return Ok(());
+    }
+}
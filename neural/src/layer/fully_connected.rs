@@ -1,9 +1,15 @@
-use crate::{layer::BackpropagationResult, ActivationFunction, Float, Layer, LayerEnum};
+use crate::layer::gradient::{
+    apply_weight_gradients, validate_weights, weight_gradient_norm_squared, WeightGradient,
+};
+use crate::layer::GradientUpdate;
+use crate::regularization::Regularization;
+use crate::{
+    layer::BackpropagationResult, ActivationFunction, Error, Float, Initializer, Layer, LayerEnum,
+};
 
 use nalgebra::{DMatrix, DVector};
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
-use std::any::Any;
 
 /// Fully connected layer
 #[derive(Clone, Serialize, Deserialize)]
@@ -19,22 +25,62 @@ impl FullyConnected {
         layer_size: usize,
         activation_function: ActivationFunction,
     ) -> FullyConnected {
-        let mut rng = thread_rng();
+        Self::with_initializers(
+            previous_layer_size,
+            layer_size,
+            activation_function,
+            Initializer::Auto,
+            Initializer::Auto,
+        )
+        .expect("Automatic initialization can't fail")
+    }
 
-        let weights = DMatrix::<Float>::zeros(layer_size, previous_layer_size);
+    /// Create a fully connected layer with explicit weight and bias initializers.
+    pub fn with_initializers(
+        previous_layer_size: usize,
+        layer_size: usize,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+    ) -> Result<FullyConnected, Error> {
+        Self::with_rng(
+            previous_layer_size,
+            layer_size,
+            activation_function,
+            weight_initializer,
+            bias_initializer,
+            &mut thread_rng(),
+        )
+    }
 
-        return FullyConnected {
-            weights: weights
-                .map(|_| activation_function.initialize_weight(previous_layer_size, &mut rng)),
-            biases: DVector::<Float>::zeros(layer_size),
+    /// Create a fully connected layer with explicit initializers, drawing random values from the given generator.
+    pub fn with_rng(
+        previous_layer_size: usize,
+        layer_size: usize,
+        activation_function: ActivationFunction,
+        weight_initializer: Initializer,
+        bias_initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Result<FullyConnected, Error> {
+        let activation_function = activation_function.for_layer(layer_size);
+
+        return Ok(FullyConnected {
+            weights: weight_initializer.weights(
+                layer_size,
+                previous_layer_size,
+                &activation_function,
+                rng,
+            )?,
+            biases: bias_initializer.biases(layer_size, previous_layer_size, rng)?,
             activation_function,
-        };
+        });
     }
 }
 
 impl Layer for FullyConnected {
-
-    fn erased(self) -> LayerEnum { LayerEnum::FullyConnected(self) }
+    fn erased(self) -> LayerEnum {
+        LayerEnum::FullyConnected(self)
+    }
 
     fn trainable(&self) -> bool {
         true
@@ -45,7 +91,7 @@ impl Layer for FullyConnected {
     }
 
     fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
-        weighted_input.map(|x| self.activation_function.function(x))
+        self.activation_function.activate(weighted_input)
     }
 
     fn back_propagate(
@@ -54,12 +100,14 @@ impl Layer for FullyConnected {
         previous_activation: &DVector<Float>,
         weighted_input: &DVector<Float>,
     ) -> Box<dyn BackpropagationResult> {
-        let error = next_error
-            .component_mul(&weighted_input.map(|x| self.activation_function.derivative(x)));
+        let error = next_error.component_mul(&self.activation_function.derivatives(weighted_input));
 
-        let result = FullyConnectedBackpropagationResult {
+        let result = WeightGradient {
             delta_weight_gradient: &error * previous_activation.transpose(),
             delta_bias_gradient: error.clone(),
+            delta_parameter_gradient: self
+                .activation_function
+                .parameter_gradient(next_error, weighted_input),
         };
 
         *next_error = self.weights.transpose() * error;
@@ -70,53 +118,56 @@ impl Layer for FullyConnected {
     fn apply_results(
         &mut self,
         results: Vec<Box<dyn BackpropagationResult>>,
-        learning_rate: Float,
+        update: &GradientUpdate,
     ) {
-        let count = results.len();
-
-        let mut weight_gradient = DMatrix::zeros(0, 0);
-        let mut bias_gradient = DVector::zeros(0);
-
-        let mut first = true;
+        apply_weight_gradients(
+            "FullyConnected",
+            results,
+            update,
+            &mut self.weights,
+            &mut self.biases,
+            &mut self.activation_function,
+        );
+    }
 
-        for result in results {
-            let result: &FullyConnectedBackpropagationResult = match result.as_any().downcast_ref()
-            {
-                Some(result) => result,
-                None => panic!(
-                    "Incompatible result type for FullyConnected layer: {:?}",
-                    result
-                ),
-            };
+    fn gradient_norm_squared(
+        &self,
+        results: &[&dyn BackpropagationResult],
+        regularization: &Regularization,
+    ) -> Float {
+        weight_gradient_norm_squared("FullyConnected", results, &self.weights, regularization)
+    }
 
-            if first {
-                weight_gradient = result.delta_weight_gradient.clone();
-                bias_gradient = result.delta_bias_gradient.clone();
+    fn size(&self) -> usize {
+        self.biases.len()
+    }
 
-                first = false;
-            } else {
-                weight_gradient += &result.delta_weight_gradient;
-                bias_gradient += &result.delta_bias_gradient;
-            }
-        }
+    fn input_size(&self) -> Option<usize> {
+        Some(self.weights.ncols())
+    }
 
-        self.weights -= weight_gradient * learning_rate / count as Float;
-        self.biases -= bias_gradient * learning_rate / count as Float;
+    fn validate(&self) -> Result<(), Error> {
+        let (output_size, input_size) = (self.size(), self.weights.ncols());
+
+        validate_weights(
+            &self.weights,
+            (output_size, input_size),
+            &self.biases,
+            output_size,
+            &self.activation_function,
+            output_size,
+        )
     }
 
-    fn size(&self) -> usize {
-        self.biases.len()
+    fn activation_function(&self) -> Option<&ActivationFunction> {
+        Some(&self.activation_function)
     }
-}
 
-#[derive(Debug)]
-struct FullyConnectedBackpropagationResult {
-    delta_weight_gradient: DMatrix<Float>,
-    delta_bias_gradient: DVector<Float>,
-}
+    fn parameter_count(&self) -> usize {
+        self.weights.len() + self.biases.len() + self.activation_function.parameter_count()
+    }
 
-impl BackpropagationResult for FullyConnectedBackpropagationResult {
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn weight_values(&self) -> Vec<&[Float]> {
+        vec![self.weights.as_slice()]
     }
 }
@@ -0,0 +1,182 @@
+use crate::layer::{PoolType, Shape};
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+/// Global pooling layer
+/// Pools every channel of the input to a single value, e.g. after the last convolution of an image network.
+/// Input is expected to be a channel major vector, i.e. the values of each channel are contiguous.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GlobalPool {
+    pub pool_type: PoolType,
+    pub channels: usize,
+    /// The amount of values per input channel
+    pub channel_size: usize,
+}
+
+impl GlobalPool {
+    pub fn new(pool_type: PoolType, input_shape: Shape) -> Self {
+        Self {
+            pool_type,
+            channels: input_shape.channels,
+            channel_size: input_shape.channel_size(),
+        }
+    }
+
+    /// A global average pooling layer, with the input shape inferred from the previous layer.
+    pub fn average() -> Self {
+        Self::new(PoolType::AVERAGE, Shape::new(0, 0, 0))
+    }
+
+    /// A global max pooling layer, with the input shape inferred from the previous layer.
+    pub fn max() -> Self {
+        Self::new(PoolType::MAX, Shape::new(0, 0, 0))
+    }
+}
+
+impl Layer for GlobalPool {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::GlobalPool(self)
+    }
+
+    fn trainable(&self) -> bool {
+        false
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        self.weighted_input(input)
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        assert_eq!(
+            input.len(),
+            self.channels * self.channel_size,
+            "Incorrect input length {}. Should be {} * {} = {}",
+            input.len(),
+            self.channels,
+            self.channel_size,
+            self.channels * self.channel_size
+        );
+
+        DVector::from_fn(self.channels, |channel, _| {
+            let values = input.rows(channel * self.channel_size, self.channel_size);
+
+            match self.pool_type {
+                PoolType::MAX => values.max(),
+                PoolType::AVERAGE => values.sum() / self.channel_size as Float,
+            }
+        })
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Activation is linear, so no Hadamard product needed
+        let mut input_error = DVector::<Float>::zeros(self.channels * self.channel_size);
+
+        for (channel, error) in next_error.iter().enumerate() {
+            let start = channel * self.channel_size;
+
+            match self.pool_type {
+                PoolType::MAX => {
+                    let values = previous_activation.rows(start, self.channel_size);
+
+                    input_error[start + values.imax()] = *error;
+                }
+                PoolType::AVERAGE => {
+                    for i in start..(start + self.channel_size) {
+                        input_error[i] = error / self.channel_size as Float;
+                    }
+                }
+            }
+        }
+
+        *next_error = input_error;
+
+        return Box::new(());
+    }
+
+    fn size(&self) -> usize {
+        self.channels
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(self.channels, 1, 1)
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.channels * self.channel_size)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        if self.channels == 0 {
+            self.channels = input_shape.channels;
+            self.channel_size = input_shape.channel_size();
+        }
+
+        return Ok(());
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.channel_size == 0 {
+            return Err(Error::InvalidParameter(
+                "Channels can't be empty".to_string(),
+            ));
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalPool, Layer, PoolType, Shape};
+    use nalgebra::DVector;
+
+    #[test]
+    fn average_pool() {
+        let input = DVector::from_vec(vec![1.0, 3.0, 2.0, 6.0, 0.0, 4.0, 8.0, 4.0]);
+        let layer = GlobalPool::new(PoolType::AVERAGE, Shape::new(2, 2, 2));
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![3.0, 4.0]
+        );
+
+        let mut error = DVector::from_vec(vec![4.0, 8.0]);
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(
+            error.data.as_vec().clone(),
+            vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn max_pool() {
+        let input = DVector::from_vec(vec![1.0, 3.0, 2.0, 6.0, 0.0, 4.0]);
+        let mut layer = GlobalPool::max();
+        layer.connect(Shape::new(3, 1, 2)).unwrap();
+
+        assert_eq!(layer.shape(), Shape::new(3, 1, 1));
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![3.0, 6.0, 4.0]
+        );
+
+        let mut error = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(
+            error.data.as_vec().clone(),
+            vec![0.0, 1.0, 0.0, 2.0, 0.0, 3.0]
+        );
+    }
+}
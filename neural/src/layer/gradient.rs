@@ -0,0 +1,216 @@
+use crate::layer::GradientUpdate;
+use crate::regularization::Regularization;
+use crate::{ActivationFunction, BackpropagationResult, Error, Float};
+
+use nalgebra::{DMatrix, DVector};
+use std::any::Any;
+
+/// Back propagation result of a layer with weights and biases
+#[derive(Debug)]
+pub(crate) struct WeightGradient {
+    pub delta_weight_gradient: DMatrix<Float>,
+    pub delta_bias_gradient: DVector<Float>,
+    /// Gradient of the learnable activation function parameters, if any
+    pub delta_parameter_gradient: Option<DVector<Float>>,
+}
+
+impl BackpropagationResult for WeightGradient {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Average the weight gradients of a batch and add the gradient of the regularization penalty.
+fn batch_gradient(
+    layer_name: &str,
+    results: &[&dyn BackpropagationResult],
+    weights: &DMatrix<Float>,
+    regularization: &Regularization,
+) -> WeightGradient {
+    let count = results.len() as Float;
+
+    let mut gradient: Option<WeightGradient> = None;
+
+    for result in results {
+        let result: &WeightGradient = match result.as_any().downcast_ref() {
+            Some(result) => result,
+            None => panic!(
+                "Incompatible result type for {} layer: {:?}",
+                layer_name, result
+            ),
+        };
+
+        match &mut gradient {
+            // On first run, gradient = 0 + delta = delta
+            None => {
+                gradient = Some(WeightGradient {
+                    delta_weight_gradient: result.delta_weight_gradient.clone(),
+                    delta_bias_gradient: result.delta_bias_gradient.clone(),
+                    delta_parameter_gradient: result.delta_parameter_gradient.clone(),
+                })
+            }
+            Some(gradient) => {
+                gradient.delta_weight_gradient += &result.delta_weight_gradient;
+                gradient.delta_bias_gradient += &result.delta_bias_gradient;
+
+                if let (Some(gradient), Some(delta)) = (
+                    &mut gradient.delta_parameter_gradient,
+                    &result.delta_parameter_gradient,
+                ) {
+                    *gradient += delta;
+                }
+            }
+        }
+    }
+
+    let mut gradient = gradient.expect("No back propagation results to apply");
+
+    gradient.delta_weight_gradient /= count;
+    gradient.delta_bias_gradient /= count;
+
+    if let Some(parameter_gradient) = &mut gradient.delta_parameter_gradient {
+        *parameter_gradient /= count;
+    }
+
+    if !regularization.is_none() {
+        gradient.delta_weight_gradient += regularization.gradient(weights);
+    }
+
+    return gradient;
+}
+
+/// The squared L2 norm of the averaged gradient of a batch, including the regularization penalty.
+pub(crate) fn weight_gradient_norm_squared(
+    layer_name: &str,
+    results: &[&dyn BackpropagationResult],
+    weights: &DMatrix<Float>,
+    regularization: &Regularization,
+) -> Float {
+    let gradient = batch_gradient(layer_name, results, weights, regularization);
+
+    return gradient.delta_weight_gradient.norm_squared()
+        + gradient.delta_bias_gradient.norm_squared()
+        + gradient
+            .delta_parameter_gradient
+            .map_or(0.0, |gradient| gradient.norm_squared());
+}
+
+/// Average the weight gradients of a batch and apply them to the parameters of a layer.
+pub(crate) fn apply_weight_gradients(
+    layer_name: &str,
+    results: Vec<Box<dyn BackpropagationResult>>,
+    update: &GradientUpdate,
+    weights: &mut DMatrix<Float>,
+    biases: &mut DVector<Float>,
+    activation_function: &mut ActivationFunction,
+) {
+    let results: Vec<&dyn BackpropagationResult> = results.iter().map(|r| r.as_ref()).collect();
+    let gradient = batch_gradient(layer_name, &results, weights, &update.regularization);
+
+    // Clip by value, then scale by the learning rate and the scale from clipping by norm
+    let step = |x: Float| {
+        let x = match update.clip_value {
+            Some(clip_value) => x.clamp(-clip_value, clip_value),
+            None => x,
+        };
+
+        x * update.scale * update.learning_rate
+    };
+
+    *weights -= gradient.delta_weight_gradient.map(step);
+    *biases -= gradient.delta_bias_gradient.map(step);
+
+    if let Some(parameter_gradient) = gradient.delta_parameter_gradient {
+        activation_function.apply_parameter_gradient(&parameter_gradient.map(step));
+    }
+}
+
+/// Check that the parameters of a layer with weights and biases have the expected dimensions.
+pub(crate) fn validate_weights(
+    weights: &DMatrix<Float>,
+    weight_shape: (usize, usize),
+    biases: &DVector<Float>,
+    bias_count: usize,
+    activation_function: &ActivationFunction,
+    layer_size: usize,
+) -> Result<(), Error> {
+    if weights.shape() != weight_shape {
+        return Err(Error::InvalidParameter(format!(
+            "Weights are of shape {:?}, should be {:?}",
+            weights.shape(),
+            weight_shape
+        )));
+    }
+
+    if biases.len() != bias_count {
+        return Err(Error::InvalidParameter(format!(
+            "There are {} biases, should be {}",
+            biases.len(),
+            bias_count
+        )));
+    }
+
+    let parameter_count = activation_function.parameter_count();
+
+    if parameter_count != 0 && parameter_count != layer_size {
+        return Err(Error::InvalidParameter(format!(
+            "Activation function has {} parameters, should be {}",
+            parameter_count, layer_size
+        )));
+    }
+
+    return Ok(());
+}
+
+/// Checks the weight gradient of a layer against a numerical gradient using central differences.
+/// The layer is followed by a fully connected sigmoid layer with two outputs,
+/// and `weights` selects the weights to check from the layer.
+#[cfg(test)]
+pub(crate) fn check_weight_gradient<L: crate::Layer + Clone + 'static>(
+    layer: L,
+    weights: fn(&mut crate::LayerEnum) -> &mut DMatrix<Float>,
+    input: &[Float],
+) {
+    use crate::layer::{FullyConnected, Input};
+    use crate::{CostFunction, Layer, Network};
+
+    let data = vec![(input.to_vec(), vec![0.3, 0.7])];
+
+    let mut network = Network::new(CostFunction::MeanSquaredError);
+
+    network.add_layer(Input::new(input.len())).unwrap();
+    network.add_layer(layer).unwrap();
+    network
+        .add_layer(FullyConnected::new(
+            network.layers[1].size(),
+            2,
+            ActivationFunction::Sigmoid,
+        ))
+        .unwrap();
+
+    let epsilon = 1e-6;
+    let original = weights(&mut network.layers[1]).clone();
+    let mut numerical_gradient = DMatrix::<Float>::zeros(original.nrows(), original.ncols());
+
+    for i in 0..original.len() {
+        weights(&mut network.layers[1])[i] = original[i] + epsilon;
+        let cost_plus = network.evaluate_loss(&data);
+
+        weights(&mut network.layers[1])[i] = original[i] - epsilon;
+        let cost_minus = network.evaluate_loss(&data);
+
+        weights(&mut network.layers[1])[i] = original[i];
+
+        numerical_gradient[i] = (cost_plus - cost_minus) / (2.0 * epsilon);
+    }
+
+    network.stochastic_gradient_descent(data, 1, 1.0);
+
+    let gradient = original - weights(&mut network.layers[1]).clone();
+
+    assert!((gradient - numerical_gradient).abs().max() < 1e-4);
+}
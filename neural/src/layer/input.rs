@@ -45,14 +45,6 @@ impl Layer for Input {
         return Box::new(());
     }
 
-    fn apply_results(
-        &mut self,
-        _results: Vec<Box<dyn BackpropagationResult>>,
-        _learning_rate: Float,
-    ) {
-        panic!("Cannot apply results to input layer");
-    }
-
     fn size(&self) -> usize {
         self.size
     }
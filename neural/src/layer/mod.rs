@@ -1,12 +1,34 @@
+mod branch;
+mod conv1d;
+mod conv_transpose2d;
+mod custom;
+mod flatten;
 mod fully_connected;
+mod global_pool;
+mod gradient;
 mod input;
+mod pool1d;
 mod pool2d;
+mod reshape;
+mod shape;
+mod upsample2d;
 
+pub use branch::{Branch, MergeType};
+pub use conv1d::{Conv1D, ConvolutionOptions};
+pub use conv_transpose2d::ConvTranspose2D;
+pub use custom::{is_registered, register_layer, CustomLayer, ErasedCustomLayer};
+pub use flatten::Flatten;
 pub use fully_connected::FullyConnected;
+pub use global_pool::GlobalPool;
 pub use input::Input;
+pub use pool1d::Pool1D;
 pub use pool2d::{Pool2D, PoolType};
+pub use reshape::Reshape;
+pub use shape::Shape;
+pub use upsample2d::{Upsample2D, UpsampleType};
 
-use crate::Float;
+use crate::regularization::Regularization;
+use crate::{ActivationFunction, Error, Float};
 use nalgebra::DVector;
 use std::any::Any;
 use std::fmt::Debug;
@@ -40,36 +62,149 @@ pub trait Layer: Sync {
         weighted_input: &DVector<Float>,
     ) -> Box<dyn BackpropagationResult>;
 
-    fn apply_results(&mut self, results: Vec<Box<dyn BackpropagationResult>>, learning_rate: Float);
+    /// Applies the back propagation results of a batch. Untrainable layers have nothing to apply.
+    fn apply_results(&mut self, _results: Vec<Box<dyn BackpropagationResult>>, _update: &GradientUpdate) {}
+
+    /// The squared L2 norm of the averaged gradient of a batch, used to clip gradients by norm.
+    fn gradient_norm_squared(
+        &self,
+        _results: &[&dyn BackpropagationResult],
+        _regularization: &Regularization,
+    ) -> Float {
+        0.0
+    }
 
     /// The size of this layer
     fn size(&self) -> usize;
+
+    /// The output shape of this layer
+    fn shape(&self) -> Shape {
+        Shape::flat(self.size())
+    }
+
+    /// The input size this layer expects, or None if it accepts any size.
+    fn input_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Called when the layer is added after a layer with the given output shape.
+    /// Layers created without input dimensions infer them here, returning an error if they can't.
+    fn connect(&mut self, _input_shape: Shape) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Checks that the parameters of this layer are consistent, e.g. after deserializing.
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The activation function of this layer, if it has one.
+    fn activation_function(&self) -> Option<&ActivationFunction> {
+        None
+    }
+
+    /// The amount of trainable parameters, including biases and activation function parameters.
+    fn parameter_count(&self) -> usize {
+        0
+    }
+
+    /// The weight values of this layer, excluding biases. Used for statistics such as in `Network::summary`.
+    fn weight_values(&self) -> Vec<&[Float]> {
+        vec![]
+    }
+}
+
+/// How the back propagation results of a batch are applied to a layer
+#[derive(Clone, Copy, Debug)]
+pub struct GradientUpdate {
+    pub learning_rate: Float,
+    /// Weight penalties of the layer, added to the gradient
+    pub regularization: Regularization,
+    /// Factor the gradients are multiplied by, e.g. when clipping by norm
+    pub scale: Float,
+    /// Gradient values are clamped to [-clip_value, clip_value]
+    pub clip_value: Option<Float>,
+}
+
+impl GradientUpdate {
+    /// A plain gradient descent step with the given learning rate
+    pub fn new(learning_rate: Float) -> Self {
+        Self {
+            learning_rate,
+            regularization: Regularization::default(),
+            scale: 1.0,
+            clip_value: None,
+        }
+    }
 }
 
 pub trait BackpropagationResult: Debug {
     fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
 }
 
 impl BackpropagationResult for () {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum LayerEnum {
     Input(Input),
     FullyConnected(FullyConnected),
-    Pool2D(Pool2D)
+    Pool2D(Pool2D),
+    Branch(Branch),
+    /// A user-defined layer, see `CustomLayer`
+    Custom(Box<dyn ErasedCustomLayer>),
+    Conv1D(Conv1D),
+    Pool1D(Pool1D),
+    ConvTranspose2D(ConvTranspose2D),
+    Upsample2D(Upsample2D),
+    GlobalPool(GlobalPool),
+    Flatten(Flatten),
+    Reshape(Reshape)
 }
 
 impl LayerEnum {
 
+    /// The name of the layer type, or the tag of a custom layer.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Input(_) => "Input",
+            Self::FullyConnected(_) => "FullyConnected",
+            Self::Pool2D(_) => "Pool2D",
+            Self::Branch(_) => "Branch",
+            Self::Custom(l) => l.tag(),
+            Self::Conv1D(_) => "Conv1D",
+            Self::Pool1D(_) => "Pool1D",
+            Self::ConvTranspose2D(_) => "ConvTranspose2D",
+            Self::Upsample2D(_) => "Upsample2D",
+            Self::GlobalPool(_) => "GlobalPool",
+            Self::Flatten(_) => "Flatten",
+            Self::Reshape(_) => "Reshape"
+        }
+    }
+
     fn reference(&self) -> &dyn Layer {
         match self {
             Self::Input(l) => l,
             Self::FullyConnected(l) => l,
-            Self::Pool2D(l) => l
+            Self::Pool2D(l) => l,
+            Self::Branch(l) => l,
+            Self::Custom(l) => l.as_layer(),
+            Self::Conv1D(l) => l,
+            Self::Pool1D(l) => l,
+            Self::ConvTranspose2D(l) => l,
+            Self::Upsample2D(l) => l,
+            Self::GlobalPool(l) => l,
+            Self::Flatten(l) => l,
+            Self::Reshape(l) => l
         }
     }
 
@@ -77,7 +212,16 @@ impl LayerEnum {
         match self {
             Self::Input(l) => l,
             Self::FullyConnected(l) => l,
-            Self::Pool2D(l) => l
+            Self::Pool2D(l) => l,
+            Self::Branch(l) => l,
+            Self::Custom(l) => l.as_layer_mut(),
+            Self::Conv1D(l) => l,
+            Self::Pool1D(l) => l,
+            Self::ConvTranspose2D(l) => l,
+            Self::Upsample2D(l) => l,
+            Self::GlobalPool(l) => l,
+            Self::Flatten(l) => l,
+            Self::Reshape(l) => l
         }
     }
 
@@ -110,11 +254,47 @@ impl Layer for LayerEnum {
         self.reference().back_propagate(next_error, previous_activation, weighted_input)
     }
 
-    fn apply_results(&mut self, results: Vec<Box<dyn BackpropagationResult>>, learning_rate: Float) {
-        self.mut_reference().apply_results(results, learning_rate)
+    fn apply_results(&mut self, results: Vec<Box<dyn BackpropagationResult>>, update: &GradientUpdate) {
+        self.mut_reference().apply_results(results, update)
+    }
+
+    fn gradient_norm_squared(
+        &self,
+        results: &[&dyn BackpropagationResult],
+        regularization: &Regularization,
+    ) -> Float {
+        self.reference().gradient_norm_squared(results, regularization)
     }
 
     fn size(&self) -> usize {
         self.reference().size()
     }
+
+    fn shape(&self) -> Shape {
+        self.reference().shape()
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.reference().input_size()
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        self.mut_reference().connect(input_shape)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        self.reference().validate()
+    }
+
+    fn activation_function(&self) -> Option<&ActivationFunction> {
+        self.reference().activation_function()
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.reference().parameter_count()
+    }
+
+    fn weight_values(&self) -> Vec<&[Float]> {
+        self.reference().weight_values()
+    }
 }
\ No newline at end of file
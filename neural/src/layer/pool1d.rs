@@ -0,0 +1,208 @@
+use crate::layer::{PoolType, Shape};
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+/// 1-dimensional (temporal) pooling layer
+/// Input is expected to be a channel major vector, i.e. the values of each channel are contiguous.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pool1D {
+    pub pool_type: PoolType,
+    pub channels: usize,
+    pub input_length: usize,
+    pub kernel_size: usize,
+    pub stride: usize,
+}
+
+impl Pool1D {
+    pub fn new(
+        pool_type: PoolType,
+        channels: usize,
+        input_length: usize,
+        kernel_size: usize,
+        stride: usize,
+    ) -> Result<Self, Error> {
+        let layer = Self {
+            pool_type,
+            channels,
+            input_length,
+            kernel_size,
+            stride,
+        };
+
+        layer.validate()?;
+
+        return Ok(layer);
+    }
+
+    /// A pooling layer with the channels and input length inferred from the previous layer.
+    pub fn inferred(pool_type: PoolType, kernel_size: usize, stride: usize) -> Self {
+        Self {
+            pool_type,
+            channels: 0,
+            input_length: 0,
+            kernel_size,
+            stride,
+        }
+    }
+
+    /// The length of each output channel
+    pub fn output_length(&self) -> usize {
+        (self.input_length - self.kernel_size) / self.stride + 1
+    }
+
+    /// The input index of the first value of a pooling window
+    fn window_start(&self, channel: usize, position: usize) -> usize {
+        channel * self.input_length + position * self.stride
+    }
+}
+
+impl Layer for Pool1D {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Pool1D(self)
+    }
+
+    fn trainable(&self) -> bool {
+        false
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        self.weighted_input(input)
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        assert_eq!(
+            input.len(),
+            self.channels * self.input_length,
+            "Incorrect input length {}. Should be {} * {} = {}",
+            input.len(),
+            self.channels,
+            self.input_length,
+            self.channels * self.input_length
+        );
+
+        let output_length = self.output_length();
+
+        DVector::from_fn(self.size(), |i, _| {
+            let window = input.rows(
+                self.window_start(i / output_length, i % output_length),
+                self.kernel_size,
+            );
+
+            match self.pool_type {
+                PoolType::MAX => window.max(),
+                PoolType::AVERAGE => window.sum() / self.kernel_size as Float,
+            }
+        })
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Activation is linear, so no Hadamard product needed
+        let mut input_error = DVector::<Float>::zeros(self.channels * self.input_length);
+
+        let output_length = self.output_length();
+
+        for (i, error) in next_error.iter().enumerate() {
+            let start = self.window_start(i / output_length, i % output_length);
+
+            match self.pool_type {
+                PoolType::MAX => {
+                    let window = previous_activation.rows(start, self.kernel_size);
+
+                    input_error[start + window.imax()] += error;
+                }
+                PoolType::AVERAGE => {
+                    for j in start..(start + self.kernel_size) {
+                        input_error[j] += error / self.kernel_size as Float;
+                    }
+                }
+            }
+        }
+
+        *next_error = input_error;
+
+        return Box::new(());
+    }
+
+    fn size(&self) -> usize {
+        self.channels * self.output_length()
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(self.channels, 1, self.output_length())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.channels * self.input_length)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        if self.channels == 0 {
+            self.channels = input_shape.channels;
+            self.input_length = input_shape.channel_size();
+        }
+
+        return self.validate();
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.stride == 0 || self.kernel_size == 0 || self.kernel_size > self.input_length {
+            return Err(Error::InvalidParameter(format!(
+                "Kernel of size {} with stride {} doesn't fit in input of length {}",
+                self.kernel_size, self.stride, self.input_length
+            )));
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layer, Pool1D, PoolType};
+    use nalgebra::DVector;
+
+    #[test]
+    fn max_pool() {
+        let input = DVector::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0, 0.0, 6.0, 1.0]);
+        let layer = Pool1D::new(PoolType::MAX, 2, 4, 2, 1).unwrap();
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![3.0, 3.0, 5.0, 4.0, 6.0, 6.0]
+        );
+
+        let mut error = DVector::from_vec(vec![1.0; 6]);
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(
+            error.data.as_vec().clone(),
+            vec![0.0, 2.0, 0.0, 1.0, 1.0, 0.0, 2.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn average_pool() {
+        let input = DVector::from_vec(vec![1.0, 3.0, 2.0, 6.0]);
+        let layer = Pool1D::new(PoolType::AVERAGE, 1, 4, 2, 2).unwrap();
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![2.0, 4.0]
+        );
+
+        let mut error = DVector::from_vec(vec![2.0, 4.0]);
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(error.data.as_vec().clone(), vec![1.0, 1.0, 2.0, 2.0]);
+    }
+}
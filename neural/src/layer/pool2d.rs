@@ -1,4 +1,5 @@
-use crate::{BackpropagationResult, Float, Layer, LayerEnum};
+use crate::layer::Shape;
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
 use nalgebra::{DMatrix, DVector};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -39,30 +40,46 @@ impl Pool2D {
         input_height: usize,
         kernel_width: usize,
         kernel_height: usize,
-    ) -> Self {
-        assert_eq!(
-            input_width % kernel_width,
-            0,
-            "Pool2D kernel width must fit in input width a whole amount of times"
-        );
-        assert_eq!(
-            input_height % kernel_height,
-            0,
-            "Pool2D kernel height must fit in input height a whole amount of times"
-        );
-
-        Self {
+    ) -> Result<Self, Error> {
+        let layer = Self {
             pool_type,
             input_width,
             input_height,
             kernel_width,
             kernel_height,
-        }
+        };
+
+        layer.validate()?;
+
+        return Ok(layer);
     }
 
-    pub fn new_square(pool_type: PoolType, input_size: usize, kernel_size: usize) -> Self {
+    pub fn new_square(
+        pool_type: PoolType,
+        input_size: usize,
+        kernel_size: usize,
+    ) -> Result<Self, Error> {
         Self::new(pool_type, input_size, input_size, kernel_size, kernel_size)
     }
+
+    /// A pooling layer with the input dimensions inferred from the previous layer.
+    pub fn inferred(pool_type: PoolType, kernel_width: usize, kernel_height: usize) -> Self {
+        Self {
+            pool_type,
+            input_width: 0,
+            input_height: 0,
+            kernel_width,
+            kernel_height,
+        }
+    }
+
+    pub fn output_width(&self) -> usize {
+        self.input_width / self.kernel_width
+    }
+
+    pub fn output_height(&self) -> usize {
+        self.input_height / self.kernel_height
+    }
 }
 
 impl Layer for Pool2D {
@@ -96,8 +113,8 @@ impl Layer for Pool2D {
 
         let mut output = DVector::<Float>::zeros(self.size());
 
-        for x in 0..(self.input_width / self.kernel_width) {
-            for y in 0..(self.input_height / self.kernel_height) {
+        for x in 0..self.output_width() {
+            for y in 0..self.output_height() {
                 let pool = input
                     .slice(
                         (y * self.kernel_height, x * self.kernel_width),
@@ -105,10 +122,10 @@ impl Layer for Pool2D {
                     )
                     .into_owned();
 
-                output[x * (self.input_width / self.kernel_width) + y] = match self.pool_type {
+                output[x * self.output_height() + y] = match self.pool_type {
                     PoolType::MAX => pool.max(),
                     PoolType::AVERAGE => {
-                        pool.sum() / (self.kernel_width * self.kernel_height) as f64
+                        pool.sum() / (self.kernel_width * self.kernel_height) as Float
                     }
                 };
             }
@@ -136,20 +153,19 @@ impl Layer for Pool2D {
             for y in 0..self.input_height {
                 let py = y / self.kernel_height;
 
-                match self.pool_type {
+                let pool = px * self.output_height() + py;
+                let index = x * self.input_height + y;
+
+                next_error_matrix[index] = match self.pool_type {
                     PoolType::MAX => {
-                        next_error_matrix[x * self.input_width + y] = if weighted_input
-                            [px * (self.input_width / self.kernel_width) + py]
-                            == previous_activation[x * self.input_width + y]
-                        {
-                            next_error[px * (self.input_width / self.kernel_width) + py]
+                        if weighted_input[pool] == previous_activation[index] {
+                            next_error[pool]
                         } else {
                             0.0
                         }
                     }
                     PoolType::AVERAGE => {
-                        next_error_matrix[x * self.input_width + y] =
-                            next_error[px * (self.input_width / self.kernel_width) + py] / 4.0
+                        next_error[pool] / (self.kernel_width * self.kernel_height) as Float
                     }
                 }
             }
@@ -160,16 +176,47 @@ impl Layer for Pool2D {
         return Box::new(());
     }
 
-    fn apply_results(
-        &mut self,
-        _results: Vec<Box<dyn BackpropagationResult>>,
-        _learning_rate: Float,
-    ) {
-        panic!("Cannot apply results to untrainable layer.")
-    }
 
     fn size(&self) -> usize {
-        (self.input_width / self.kernel_width) * (self.input_height / self.kernel_height)
+        self.output_width() * self.output_height()
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(1, self.output_height(), self.output_width())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.input_width * self.input_height)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        if self.input_width == 0 {
+            if input_shape.channels != 1 {
+                return Err(Error::InvalidParameter(
+                    "Pool2D input must be a single channel image".to_string(),
+                ));
+            }
+
+            self.input_width = input_shape.width;
+            self.input_height = input_shape.height;
+        }
+
+        return self.validate();
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.kernel_width == 0
+            || self.kernel_height == 0
+            || !self.input_width.is_multiple_of(self.kernel_width)
+            || !self.input_height.is_multiple_of(self.kernel_height)
+        {
+            return Err(Error::InvalidParameter(format!(
+                "Kernel of {}x{} must fit in the {}x{} input a whole amount of times",
+                self.kernel_width, self.kernel_height, self.input_width, self.input_height
+            )));
+        }
+
+        return Ok(());
     }
 }
 
@@ -190,7 +237,7 @@ mod tests {
 
         let input = DVector::from_vec(input_image.data.as_slice().to_vec());
 
-        let layer = Pool2D::new_square(PoolType::MAX, 4, 2);
+        let layer = Pool2D::new_square(PoolType::MAX, 4, 2).unwrap();
 
         assert_eq!(
             layer.feed_forward(&input).data.as_vec().clone(),
@@ -204,7 +251,7 @@ mod tests {
 
         let input = DVector::from_vec(input_image.data.as_slice().to_vec());
 
-        let layer = Pool2D::new_square(PoolType::AVERAGE, 4, 2);
+        let layer = Pool2D::new_square(PoolType::AVERAGE, 4, 2).unwrap();
 
         assert_eq!(
             layer.feed_forward(&input).data.as_vec().clone(),
@@ -220,7 +267,7 @@ mod tests {
         let weighted_input = DVector::from_vec(vec![6.0, 14.0, 8.0, 16.0]);
         let mut error = DVector::from_vec(vec![4.0, 4.0, 4.0, 4.0]);
 
-        let layer = Pool2D::new_square(PoolType::MAX, 4, 2);
+        let layer = Pool2D::new_square(PoolType::MAX, 4, 2).unwrap();
 
         layer.back_propagate(&mut error, &previous_activation, &weighted_input);
 
@@ -238,10 +285,47 @@ mod tests {
         let weighted_input = DVector::from_vec(vec![3.5, 11.5, 5.5, 13.5]);
         let mut error = DVector::from_vec(vec![4.0, 4.0, 4.0, 4.0]);
 
-        let layer = Pool2D::new_square(PoolType::AVERAGE, 4, 2);
+        let layer = Pool2D::new_square(PoolType::AVERAGE, 4, 2).unwrap();
 
         layer.back_propagate(&mut error, &previous_activation, &weighted_input);
 
         assert_eq!(error.data.as_vec().clone(), vec![1.0; 16])
     }
+
+    #[test]
+    fn invalid_kernel() {
+        assert!(Pool2D::new_square(PoolType::MAX, 4, 3).is_err());
+    }
+
+    #[test]
+    fn non_square() {
+        // A 6 wide and 2 high image, stored column by column
+        let input = DVector::from_vec((1..=12).map(|x| x as Float).collect());
+
+        let layer = Pool2D::new(PoolType::AVERAGE, 6, 2, 3, 1).unwrap();
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![3.0, 4.0, 9.0, 10.0]
+        );
+
+        let mut error = DVector::from_vec(vec![3.0, 6.0, 9.0, 12.0]);
+
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(
+            error.data.as_vec().clone(),
+            vec![1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 3.0, 4.0, 3.0, 4.0, 3.0, 4.0]
+        );
+
+        let layer = Pool2D::new(PoolType::MAX, 6, 2, 3, 1).unwrap();
+        let mut error = DVector::from_vec(vec![1.0; 4]);
+
+        layer.back_propagate(&mut error, &input, &layer.feed_forward(&input));
+
+        assert_eq!(
+            error.data.as_vec().clone(),
+            vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0]
+        );
+    }
 }
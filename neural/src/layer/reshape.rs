@@ -0,0 +1,105 @@
+use crate::layer::Shape;
+use crate::{BackpropagationResult, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+
+/// Reshape layer
+/// Passes the values through unchanged, but gives them a new shape, e.g. to mark a flat input as an image.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Reshape {
+    pub shape: Shape,
+}
+
+impl Reshape {
+    pub fn new(shape: Shape) -> Self {
+        Self { shape }
+    }
+
+    pub fn image(channels: usize, height: usize, width: usize) -> Self {
+        Self::new(Shape::new(channels, height, width))
+    }
+}
+
+impl Layer for Reshape {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Reshape(self)
+    }
+
+    fn trainable(&self) -> bool {
+        false
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        input.clone()
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        input.clone()
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        _next_error: &mut DVector<Float>,
+        _previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Values are unchanged, so the error passes through as well
+        return Box::new(());
+    }
+
+    fn size(&self) -> usize {
+        self.shape.size()
+    }
+
+    fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.shape.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reshape, Shape};
+    use crate::layer::{Input, Pool2D, PoolType};
+    use crate::{CostFunction, Error, Layer, Network};
+
+    #[test]
+    fn infers_image_shape() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(16)).unwrap();
+        network.add_layer(Reshape::image(1, 4, 4)).unwrap();
+        network
+            .add_layer(Pool2D::inferred(PoolType::MAX, 2, 2))
+            .unwrap();
+
+        assert_eq!(network.layers[2].shape(), Shape::new(1, 2, 2));
+        assert_eq!(
+            network.feed_forward((1..=16).map(|x| x as _).collect()),
+            vec![6.0, 8.0, 14.0, 16.0]
+        );
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(16)).unwrap();
+
+        assert_eq!(
+            network.add_layer(Reshape::image(1, 3, 5)),
+            Err(Error::SizeMismatch {
+                layer: 1,
+                expected: 15,
+                actual: 16
+            })
+        );
+    }
+}
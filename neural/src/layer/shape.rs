@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Output shape of a layer
+/// Values are stored channel major, with every channel a column major image of height * width.
+/// Flat vectors are a single channel with a height of 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shape {
+    pub channels: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Shape {
+    pub fn new(channels: usize, height: usize, width: usize) -> Self {
+        Self {
+            channels,
+            height,
+            width,
+        }
+    }
+
+    /// A flat vector of the given size
+    pub fn flat(size: usize) -> Self {
+        Self::new(1, 1, size)
+    }
+
+    /// The amount of values per channel
+    pub fn channel_size(&self) -> usize {
+        self.height * self.width
+    }
+
+    /// The total amount of values
+    pub fn size(&self) -> usize {
+        self.channels * self.height * self.width
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}x{}", self.channels, self.height, self.width)
+    }
+}
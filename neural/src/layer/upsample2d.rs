@@ -0,0 +1,272 @@
+use crate::layer::Shape;
+use crate::{BackpropagationResult, Error, Float, Layer, LayerEnum};
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// 2-dimensional upsampling layer
+/// Input and output are expected to be channel major vectors of column major images, like `Pool2D`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Upsample2D {
+    pub upsample_type: UpsampleType,
+    pub channels: usize,
+    pub input_width: usize,
+    pub input_height: usize,
+    pub scale_width: usize,
+    pub scale_height: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum UpsampleType {
+    NEAREST,
+    /// Bilinear interpolation between pixel centers, with edge pixels clamped
+    BILINEAR,
+}
+
+impl FromStr for UpsampleType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Self::NEAREST),
+            "bilinear" | "linear" => Ok(Self::BILINEAR),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Upsample2D {
+    pub fn new(
+        upsample_type: UpsampleType,
+        channels: usize,
+        input_width: usize,
+        input_height: usize,
+        scale_width: usize,
+        scale_height: usize,
+    ) -> Result<Self, Error> {
+        let layer = Self {
+            upsample_type,
+            channels,
+            input_width,
+            input_height,
+            scale_width,
+            scale_height,
+        };
+
+        layer.validate()?;
+
+        return Ok(layer);
+    }
+
+    pub fn new_square(
+        upsample_type: UpsampleType,
+        channels: usize,
+        input_size: usize,
+        scale: usize,
+    ) -> Result<Self, Error> {
+        Self::new(
+            upsample_type,
+            channels,
+            input_size,
+            input_size,
+            scale,
+            scale,
+        )
+    }
+
+    /// An upsampling layer with the input shape inferred from the previous layer.
+    pub fn inferred(upsample_type: UpsampleType, scale_width: usize, scale_height: usize) -> Self {
+        Self {
+            upsample_type,
+            channels: 0,
+            input_width: 0,
+            input_height: 0,
+            scale_width,
+            scale_height,
+        }
+    }
+
+    pub fn output_width(&self) -> usize {
+        self.input_width * self.scale_width
+    }
+
+    pub fn output_height(&self) -> usize {
+        self.input_height * self.scale_height
+    }
+
+    /// The input positions and weights an output position along one dimension is interpolated from.
+    fn interpolate(
+        &self,
+        output_position: usize,
+        scale: usize,
+        input_size: usize,
+    ) -> [(usize, Float); 2] {
+        match self.upsample_type {
+            UpsampleType::NEAREST => [(output_position / scale, 1.0), (0, 0.0)],
+            UpsampleType::BILINEAR => {
+                let source = ((output_position as Float + 0.5) / scale as Float - 0.5).max(0.0);
+
+                let low = (source.floor() as usize).min(input_size - 1);
+                let high = (low + 1).min(input_size - 1);
+                let fraction = source - low as Float;
+
+                [(low, 1.0 - fraction), (high, fraction)]
+            }
+        }
+    }
+
+    /// Calls f with the input index, output index and weight of every contribution.
+    fn for_each_contribution(&self, mut f: impl FnMut(usize, usize, Float)) {
+        let (output_width, output_height) = (self.output_width(), self.output_height());
+
+        for channel in 0..self.channels {
+            for ox in 0..output_width {
+                let xs = self.interpolate(ox, self.scale_width, self.input_width);
+
+                for oy in 0..output_height {
+                    let ys = self.interpolate(oy, self.scale_height, self.input_height);
+                    let output = channel * output_width * output_height + ox * output_height + oy;
+
+                    for (x, wx) in xs {
+                        for (y, wy) in ys {
+                            let input = channel * self.input_width * self.input_height
+                                + x * self.input_height
+                                + y;
+
+                            f(input, output, wx * wy);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Layer for Upsample2D {
+    fn erased(self) -> LayerEnum {
+        LayerEnum::Upsample2D(self)
+    }
+
+    fn trainable(&self) -> bool {
+        false
+    }
+
+    fn feed_forward(&self, input: &DVector<Float>) -> DVector<Float> {
+        self.weighted_input(input)
+    }
+
+    fn weighted_input(&self, input: &DVector<Float>) -> DVector<Float> {
+        assert_eq!(
+            input.len(),
+            self.channels * self.input_width * self.input_height,
+            "Incorrect input length {}. Should be {} * {} * {} = {}",
+            input.len(),
+            self.channels,
+            self.input_width,
+            self.input_height,
+            self.channels * self.input_width * self.input_height
+        );
+
+        let mut output = DVector::<Float>::zeros(self.size());
+
+        self.for_each_contribution(|i, o, weight| output[o] += weight * input[i]);
+
+        return output;
+    }
+
+    fn activation(&self, weighted_input: &DVector<Float>) -> DVector<Float> {
+        weighted_input.clone()
+    }
+
+    fn back_propagate(
+        &self,
+        next_error: &mut DVector<Float>,
+        _previous_activation: &DVector<Float>,
+        _weighted_input: &DVector<Float>,
+    ) -> Box<dyn BackpropagationResult> {
+        // Activation is linear, so no Hadamard product needed
+        let mut input_error =
+            DVector::<Float>::zeros(self.channels * self.input_width * self.input_height);
+
+        self.for_each_contribution(|i, o, weight| input_error[i] += weight * next_error[o]);
+
+        *next_error = input_error;
+
+        return Box::new(());
+    }
+
+    fn size(&self) -> usize {
+        self.channels * self.output_width() * self.output_height()
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::new(self.channels, self.output_height(), self.output_width())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.channels * self.input_width * self.input_height)
+    }
+
+    fn connect(&mut self, input_shape: Shape) -> Result<(), Error> {
+        if self.channels == 0 {
+            self.channels = input_shape.channels;
+            self.input_width = input_shape.width;
+            self.input_height = input_shape.height;
+        }
+
+        return self.validate();
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.scale_width == 0 || self.scale_height == 0 {
+            return Err(Error::InvalidParameter(
+                "Scale must be at least 1".to_string(),
+            ));
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, Layer, Upsample2D, UpsampleType};
+    use nalgebra::DVector;
+
+    #[test]
+    fn nearest_feed_forward() {
+        // Column major 2x2 image [[1, 2], [3, 4]]
+        let input = DVector::from_vec(vec![1.0, 3.0, 2.0, 4.0]);
+        let layer = Upsample2D::new(UpsampleType::NEAREST, 1, 2, 2, 2, 1).unwrap();
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![1.0, 3.0, 1.0, 3.0, 2.0, 4.0, 2.0, 4.0]
+        )
+    }
+
+    #[test]
+    fn bilinear_feed_forward() {
+        let input = DVector::from_vec(vec![0.0, 4.0]);
+        let layer = Upsample2D::new(UpsampleType::BILINEAR, 1, 2, 1, 2, 1).unwrap();
+
+        assert_eq!(
+            layer.feed_forward(&input).data.as_vec().clone(),
+            vec![0.0, 1.0, 3.0, 4.0]
+        )
+    }
+
+    #[test]
+    fn bilinear_back_propagation() {
+        // The backward pass must be the transpose of the forward pass: <Ax, y> = <x, A^T y>
+        let layer = Upsample2D::new_square(UpsampleType::BILINEAR, 2, 3, 2).unwrap();
+
+        let x = DVector::<Float>::from_fn(18, |i, _| (i as Float * 0.37).sin());
+        let y = DVector::<Float>::from_fn(72, |i, _| (i as Float * 0.13).cos());
+
+        let mut error = y.clone();
+        layer.back_propagate(&mut error, &x, &layer.weighted_input(&x));
+
+        assert!((layer.feed_forward(&x).dot(&y) - x.dot(&error)).abs() < 1e-10);
+    }
+}
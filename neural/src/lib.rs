@@ -1,14 +1,32 @@
+#![allow(clippy::needless_return)]
+
 extern crate core;
 
 pub mod activation_function;
 pub mod back_propagation;
+pub mod builder;
 pub mod cost_function;
+pub mod error;
+pub mod initializer;
 pub mod layer;
+pub mod metadata;
+pub mod metrics;
 pub mod network;
+pub mod preprocessing;
+pub mod regularization;
+pub mod schedule;
+pub mod summary;
+pub mod trainer;
 
 pub use self::{
-    activation_function::ActivationFunction, cost_function::CostFunction,
-    layer::BackpropagationResult, layer::Layer, layer::LayerEnum, network::Network,
+    activation_function::ActivationFunction, back_propagation::TrainingOptions,
+    builder::LayerSpec, builder::ModelSpec, builder::NetworkBuilder, cost_function::CostFunction,
+    error::Error, initializer::Initializer, layer::BackpropagationResult, layer::GradientUpdate,
+    layer::Layer, layer::LayerEnum, metadata::Metadata, metrics::ClassificationReport,
+    metrics::RegressionReport, network::Network, preprocessing::Normalization,
+    preprocessing::Preprocessing, regularization::GradientClipping, regularization::Regularization,
+    schedule::LearningRateSchedule, schedule::LearningRateScheduler, summary::NetworkSummary,
+    trainer::Trainer,
 };
 
 #[cfg(feature = "high-precision")]
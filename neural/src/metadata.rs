@@ -0,0 +1,51 @@
+use crate::Float;
+
+use serde::{Deserialize, Serialize};
+
+/// Descriptive information stored with a network, which doesn't affect its outputs.
+/// Fields are never skipped when serializing, because networks are encoded with bincode.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub description: Option<String>,
+    /// The names of the outputs, e.g. the letters of a classifier
+    pub class_names: Vec<String>,
+    /// How raw inputs are normalized before they're fed forward, described from the preprocessing, e.g. `scale(0.00392156862745098)`.
+    /// Only informative, `Network::preprocessing` holds the transforms that are applied.
+    pub input_normalization: Option<String>,
+    /// The width and height of image inputs
+    pub image_dimensions: Option<(usize, usize)>,
+    /// The dataset preset the network was created or trained with, e.g. `emnist-letters`.
+    /// Images of a preset are read upright, even if the dataset stores them transposed.
+    pub preset: Option<String>,
+    /// Every training session of the network, oldest first
+    pub training: Vec<TrainingRun>,
+}
+
+/// The provenance of a single training session
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingRun {
+    /// The name of the training dataset, e.g. its file name
+    pub dataset: String,
+    /// The amount of epochs that were trained
+    pub epochs: usize,
+    /// The final training loss
+    pub loss: Option<Float>,
+    /// The final validation accuracy, if the network was validated
+    pub accuracy: Option<Float>,
+}
+
+impl Metadata {
+    /// The name of an output, or its index if it has no name.
+    pub fn class_name(&self, index: usize) -> String {
+        match self.class_names.get(index) {
+            Some(name) => name.clone(),
+            None => index.to_string(),
+        }
+    }
+
+    /// The total amount of epochs of all training sessions
+    pub fn trained_epochs(&self) -> usize {
+        self.training.iter().map(|run| run.epochs).sum()
+    }
+}
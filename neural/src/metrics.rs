@@ -0,0 +1,534 @@
+use crate::{Float, Network};
+
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Counts of the predicted class for every actual class
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ConfusionMatrix {
+    /// The counts, indexed by actual class and then predicted class
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(classes: usize) -> Self {
+        Self {
+            counts: vec![vec![0; classes]; classes],
+        }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn add(&mut self, actual: usize, predicted: usize) {
+        self.counts[actual][predicted] += 1;
+    }
+
+    pub fn get(&self, actual: usize, predicted: usize) -> usize {
+        self.counts[actual][predicted]
+    }
+
+    /// The amount of samples of the actual class
+    pub fn support(&self, class: usize) -> usize {
+        self.counts[class].iter().sum()
+    }
+
+    /// The amount of samples predicted as the class
+    pub fn predicted(&self, class: usize) -> usize {
+        self.counts.iter().map(|row| row[class]).sum()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn correct(&self) -> usize {
+        (0..self.classes())
+            .map(|class| self.get(class, class))
+            .sum()
+    }
+
+    /// The pairs of actual and predicted classes that are confused most often, with their counts, most frequent first.
+    pub fn most_confused(&self, count: usize) -> Vec<(usize, usize, usize)> {
+        let mut pairs: Vec<(usize, usize, usize)> = self
+            .counts
+            .iter()
+            .enumerate()
+            .flat_map(|(actual, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(move |(predicted, count)| *predicted != actual && **count > 0)
+                    .map(move |(predicted, count)| (actual, predicted, *count))
+            })
+            .collect();
+
+        pairs.sort_by_key(|pair| std::cmp::Reverse(pair.2));
+        pairs.truncate(count);
+
+        return pairs;
+    }
+}
+
+/// Precision, recall and F1 score of a single class, or an average of them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct ClassMetrics {
+    pub precision: Float,
+    pub recall: Float,
+    pub f1: Float,
+    /// The amount of samples of the class
+    pub support: usize,
+}
+
+impl ClassMetrics {
+    fn new(true_positives: usize, predicted: usize, support: usize) -> Self {
+        let ratio = |a: usize, b: usize| {
+            if b == 0 {
+                0.0
+            } else {
+                a as Float / b as Float
+            }
+        };
+
+        let precision = ratio(true_positives, predicted);
+        let recall = ratio(true_positives, support);
+        let f1 = if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        };
+
+        return Self {
+            precision,
+            recall,
+            f1,
+            support,
+        };
+    }
+}
+
+/// Evaluation of a classification network, see `ClassificationReport::new`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClassificationReport {
+    /// The name of every class, defaulting to the class index
+    pub labels: Vec<String>,
+    pub confusion_matrix: ConfusionMatrix,
+    pub classes: Vec<ClassMetrics>,
+    /// The unweighted mean of the classes that occur in the data or the predictions
+    pub macro_average: ClassMetrics,
+    /// The metrics of all predictions together
+    pub micro_average: ClassMetrics,
+    pub accuracy: Float,
+    /// The fraction of samples where the actual class is among the k largest outputs, for every k
+    pub top_k: Vec<(usize, Float)>,
+    /// The average negative log of the output of the actual class, with the outputs normalized to sum to 1
+    pub log_loss: Float,
+}
+
+impl ClassificationReport {
+    /// Creates a report from pairs of network outputs and actual classes, where the predicted class is the largest output.
+    pub fn new(predictions: &[(Vec<Float>, usize)], top_k: &[usize]) -> Self {
+        let classes = predictions
+            .iter()
+            .map(|(output, class)| output.len().max(class + 1))
+            .max()
+            .unwrap_or(0);
+
+        let mut confusion_matrix = ConfusionMatrix::new(classes);
+        let mut top_k_correct = vec![0; top_k.len()];
+        let mut log_loss = 0.0;
+
+        for (output, class) in predictions {
+            let mut ranking: Vec<usize> = (0..output.len()).collect();
+            ranking.sort_by(|a, b| output[*b].total_cmp(&output[*a]));
+
+            confusion_matrix.add(*class, ranking.first().copied().unwrap_or(0));
+
+            for (correct, k) in top_k_correct.iter_mut().zip(top_k) {
+                if ranking.iter().take(*k).any(|i| i == class) {
+                    *correct += 1;
+                }
+            }
+
+            let sum: Float = output.iter().map(|x| x.max(0.0)).sum();
+            let probability = match output.get(*class) {
+                Some(x) if sum > 0.0 => x.max(0.0) / sum,
+                _ => 0.0,
+            };
+
+            log_loss -= probability.clamp(1e-15, 1.0).ln();
+        }
+
+        let class_metrics: Vec<ClassMetrics> = (0..classes)
+            .map(|class| {
+                ClassMetrics::new(
+                    confusion_matrix.get(class, class),
+                    confusion_matrix.predicted(class),
+                    confusion_matrix.support(class),
+                )
+            })
+            .collect();
+
+        let occurring: Vec<&ClassMetrics> = class_metrics
+            .iter()
+            .enumerate()
+            .filter(|(class, metrics)| {
+                metrics.support > 0 || confusion_matrix.predicted(*class) > 0
+            })
+            .map(|(_, metrics)| metrics)
+            .collect();
+
+        let mean = |f: fn(&ClassMetrics) -> Float| {
+            occurring.iter().map(|m| f(m)).sum::<Float>() / occurring.len().max(1) as Float
+        };
+
+        let total = confusion_matrix.total();
+        let count = total.max(1) as Float;
+
+        return Self {
+            labels: (0..classes).map(|class| class.to_string()).collect(),
+            macro_average: ClassMetrics {
+                precision: mean(|m| m.precision),
+                recall: mean(|m| m.recall),
+                f1: mean(|m| m.f1),
+                support: total,
+            },
+            micro_average: ClassMetrics::new(confusion_matrix.correct(), total, total),
+            accuracy: confusion_matrix.correct() as Float / count,
+            top_k: top_k
+                .iter()
+                .zip(top_k_correct)
+                .map(|(k, correct)| (*k, correct as Float / count))
+                .collect(),
+            log_loss: log_loss / count,
+            confusion_matrix,
+            classes: class_metrics,
+        };
+    }
+
+    /// Evaluates the network on pairs of raw inputs and actual classes, applying the preprocessing of the network.
+    pub fn evaluate(network: &Network, data: &[(Vec<Float>, usize)], top_k: &[usize]) -> Self {
+        let predictions: Vec<(Vec<Float>, usize)> = data
+            .iter()
+            .map(|(input, class)| (network.predict(input.clone()), *class))
+            .collect();
+
+        return Self::new(&predictions, top_k);
+    }
+
+    /// Names the classes, e.g. with letters. Classes without a name keep their index.
+    pub fn with_labels(mut self, labels: &[String]) -> Self {
+        for (label, name) in self.labels.iter_mut().zip(labels) {
+            *label = name.clone();
+        }
+
+        return self;
+    }
+
+    /// The metrics and confusion matrix row of every class, followed by the averages, as CSV.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("class,precision,recall,f1,support");
+
+        for label in &self.labels {
+            csv += &format!(",{}", csv_field(label));
+        }
+
+        csv.push('\n');
+
+        let mut write_row = |label: &str, metrics: &ClassMetrics, counts: Option<&Vec<usize>>| {
+            csv += &format!(
+                "{},{},{},{},{}",
+                csv_field(label),
+                metrics.precision,
+                metrics.recall,
+                metrics.f1,
+                metrics.support
+            );
+
+            for i in 0..self.labels.len() {
+                match counts {
+                    Some(counts) => csv += &format!(",{}", counts[i]),
+                    None => csv.push(','),
+                }
+            }
+
+            csv.push('\n');
+        };
+
+        for (i, metrics) in self.classes.iter().enumerate() {
+            write_row(
+                &self.labels[i],
+                metrics,
+                Some(&self.confusion_matrix.counts[i]),
+            );
+        }
+
+        write_row("macro average", &self.macro_average, None);
+        write_row("micro average", &self.micro_average, None);
+
+        return csv;
+    }
+}
+
+/// Quotes a CSV field if needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Display for ClassificationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .labels
+            .iter()
+            .map(|l| l.len())
+            .chain(["Micro average".len()])
+            .max()
+            .unwrap_or(0);
+
+        let write_row = |f: &mut Formatter<'_>, label: &str, metrics: &ClassMetrics| {
+            writeln!(
+                f,
+                "{:<width$}  {:>9.4}  {:>9.4}  {:>9.4}  {:>7}",
+                label,
+                metrics.precision,
+                metrics.recall,
+                metrics.f1,
+                metrics.support,
+                width = width
+            )
+        };
+
+        writeln!(
+            f,
+            "{:<width$}  {:>9}  {:>9}  {:>9}  {:>7}",
+            "Class",
+            "Precision",
+            "Recall",
+            "F1",
+            "Support",
+            width = width
+        )?;
+
+        for (class, (label, metrics)) in self.labels.iter().zip(&self.classes).enumerate() {
+            if metrics.support > 0 || self.confusion_matrix.predicted(class) > 0 {
+                write_row(f, label, metrics)?;
+            }
+        }
+
+        writeln!(f)?;
+        write_row(f, "Macro average", &self.macro_average)?;
+        write_row(f, "Micro average", &self.micro_average)?;
+        writeln!(f)?;
+
+        writeln!(f, "Accuracy: {:.2}%", self.accuracy * 100.0)?;
+
+        for (k, accuracy) in &self.top_k {
+            writeln!(f, "Top-{} accuracy: {:.2}%", k, accuracy * 100.0)?;
+        }
+
+        write!(f, "Log loss: {:.4}", self.log_loss)?;
+
+        let confused = self.confusion_matrix.most_confused(10);
+
+        if !confused.is_empty() {
+            writeln!(f)?;
+            writeln!(f)?;
+            write!(f, "Most confused (actual -> predicted):")?;
+
+            for (actual, predicted, count) in confused {
+                write!(
+                    f,
+                    "\n{} -> {}: {}",
+                    self.labels[actual], self.labels[predicted], count
+                )?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Evaluation of a regression network, over all outputs of all samples, see `RegressionReport::new`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct RegressionReport {
+    pub mean_squared_error: Float,
+    pub root_mean_squared_error: Float,
+    pub mean_absolute_error: Float,
+    /// The coefficient of determination, averaged over the outputs
+    pub r2: Float,
+    /// The largest absolute error of any output
+    pub max_error: Float,
+    /// The amount of samples
+    pub count: usize,
+}
+
+impl RegressionReport {
+    /// Creates a report from pairs of network outputs and targets.
+    pub fn new(predictions: &[(Vec<Float>, Vec<Float>)]) -> Self {
+        let outputs = predictions.first().map_or(0, |(output, _)| output.len());
+        let values = (predictions.len() * outputs).max(1) as Float;
+
+        let errors = || {
+            predictions
+                .iter()
+                .flat_map(|(output, target)| output.iter().zip(target).map(|(o, t)| o - t))
+        };
+
+        let mean_squared_error = errors().map(|e| e * e).sum::<Float>() / values;
+
+        // R² of every output, as 1 - residual sum of squares / total sum of squares
+        let r2 = (0..outputs)
+            .map(|i| {
+                let mean = predictions.iter().map(|(_, t)| t[i]).sum::<Float>()
+                    / predictions.len() as Float;
+                let residual: Float = predictions.iter().map(|(o, t)| (o[i] - t[i]).powi(2)).sum();
+                let total: Float = predictions.iter().map(|(_, t)| (t[i] - mean).powi(2)).sum();
+
+                if total > 0.0 {
+                    1.0 - residual / total
+                } else if residual == 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .sum::<Float>()
+            / outputs.max(1) as Float;
+
+        return Self {
+            mean_squared_error,
+            root_mean_squared_error: mean_squared_error.sqrt(),
+            mean_absolute_error: errors().map(|e| e.abs()).sum::<Float>() / values,
+            r2,
+            max_error: errors().map(|e| e.abs()).fold(0.0, Float::max),
+            count: predictions.len(),
+        };
+    }
+
+    /// Evaluates the network on pairs of raw inputs and targets, applying the preprocessing of the network.
+    pub fn evaluate(network: &Network, data: &[(Vec<Float>, Vec<Float>)]) -> Self {
+        let predictions: Vec<(Vec<Float>, Vec<Float>)> = data
+            .iter()
+            .map(|(input, target)| (network.predict(input.clone()), target.clone()))
+            .collect();
+
+        return Self::new(&predictions);
+    }
+}
+
+impl Display for RegressionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Samples: {}", self.count)?;
+        writeln!(f, "Mean squared error: {:.6}", self.mean_squared_error)?;
+        writeln!(
+            f,
+            "Root mean squared error: {:.6}",
+            self.root_mean_squared_error
+        )?;
+        writeln!(f, "Mean absolute error: {:.6}", self.mean_absolute_error)?;
+        writeln!(f, "R²: {:.4}", self.r2)?;
+        write!(f, "Max error: {:.6}", self.max_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClassificationReport, ConfusionMatrix, RegressionReport};
+    use crate::Float;
+
+    fn report() -> ClassificationReport {
+        let predictions = vec![
+            (vec![0.9, 0.1, 0.0], 0),
+            (vec![0.6, 0.4, 0.0], 0),
+            (vec![0.0, 0.7, 0.3], 0),
+            (vec![0.2, 0.8, 0.0], 1),
+            (vec![0.1, 0.3, 0.6], 1),
+            (vec![0.0, 0.6, 0.4], 2),
+        ];
+
+        return ClassificationReport::new(&predictions, &[1, 2]);
+    }
+
+    #[test]
+    fn confusion_matrix() {
+        let report = report();
+
+        assert_eq!(
+            report.confusion_matrix.counts,
+            vec![vec![2, 1, 0], vec![0, 1, 1], vec![0, 1, 0]]
+        );
+        assert_eq!(
+            report.confusion_matrix.most_confused(2),
+            vec![(0, 1, 1), (1, 2, 1)]
+        );
+
+        let mut matrix = ConfusionMatrix::new(2);
+        matrix.add(1, 0);
+
+        assert_eq!(
+            (matrix.support(1), matrix.predicted(0), matrix.correct()),
+            (1, 1, 0)
+        );
+    }
+
+    #[test]
+    fn class_metrics() {
+        let report = report();
+        let close = |a: Float, b: Float| (a - b).abs() < 1e-9;
+
+        // Class 1 is predicted 3 times, of which once correct, and occurs twice
+        assert!(close(report.classes[1].precision, 1.0 / 3.0));
+        assert!(close(report.classes[1].recall, 0.5));
+        assert!(close(report.classes[1].f1, 0.4));
+        assert_eq!(report.classes[2].f1, 0.0);
+
+        assert!(close(report.accuracy, 0.5));
+        assert!(close(report.micro_average.f1, 0.5));
+        assert!(close(report.macro_average.recall, (2.0 / 3.0 + 0.5) / 3.0));
+        assert_eq!(report.top_k[0], (1, report.accuracy));
+        assert!(close(report.top_k[1].1, 5.0 / 6.0));
+    }
+
+    #[test]
+    fn log_loss() {
+        let report = ClassificationReport::new(&[(vec![1.0, 1.0], 0), (vec![2.0, 0.0], 0)], &[]);
+
+        assert!((report.log_loss - (2.0 as Float).ln() / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn csv() {
+        let report = ClassificationReport::new(&[(vec![1.0, 0.0], 0), (vec![1.0, 0.0], 1)], &[])
+            .with_labels(&["a".to_string(), "b,c".to_string()]);
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "class,precision,recall,f1,support,a,\"b,c\"");
+        assert!(lines[1].starts_with("a,0.5,1,0.666"));
+        assert_eq!(lines[2], "\"b,c\",0,0,0,1,1,0");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn regression() {
+        let report = RegressionReport::new(&[
+            (vec![1.0, 0.0], vec![1.0, 1.0]),
+            (vec![2.5, 2.0], vec![2.0, 2.0]),
+            (vec![3.0, 3.0], vec![3.0, 3.0]),
+        ]);
+
+        assert_eq!(report.count, 3);
+        assert_eq!(report.mean_squared_error, 1.25 / 6.0);
+        assert_eq!(report.mean_absolute_error, 1.5 / 6.0);
+        assert_eq!(report.max_error, 1.0);
+        assert!((report.root_mean_squared_error - (1.25 as Float / 6.0).sqrt()).abs() < 1e-9);
+
+        // Both outputs have a total sum of squares of 2
+        assert!((report.r2 - (0.875 + 0.5) / 2.0).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{CostFunction, Float, Layer, LayerEnum};
+use crate::preprocessing::Transform;
+use crate::{CostFunction, Error, Float, Layer, LayerEnum, Metadata, Preprocessing};
 
 use nalgebra::DVector;
 
-#[derive(bincode::Encode, bincode::Decode)]
+#[cfg(feature = "threads")]
+use crossbeam_utils::thread;
+
+#[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct Network {
     /// The layers in the network.
     #[bincode(with_serde)]
@@ -10,6 +14,22 @@ pub struct Network {
 
     /// The cost function for the network.
     pub cost_function: CostFunction,
+
+    /// Descriptive information, e.g. class names and training provenance.
+    #[bincode(with_serde)]
+    pub metadata: Metadata,
+
+    /// The transforms applied to inputs by `Network::predict`.
+    #[bincode(with_serde)]
+    pub preprocessing: Preprocessing,
+}
+
+/// The encoding of networks from before metadata and preprocessing were added, which can still be decoded.
+#[derive(bincode::Encode, bincode::Decode)]
+struct LegacyNetwork {
+    #[bincode(with_serde)]
+    layers: Vec<LayerEnum>,
+    cost_function: CostFunction,
 }
 
 impl Network {
@@ -17,17 +37,180 @@ impl Network {
         return Self {
             layers: vec![],
             cost_function,
+            metadata: Metadata::default(),
+            preprocessing: Preprocessing::default(),
+        };
+    }
+
+    /// Adds a layer to the end of the network.
+    /// Layers created without input dimensions infer them from the output shape of the previous layer.
+    /// Returns an error if the layer doesn't fit after the previous layer.
+    pub fn add_layer<L: Layer + Clone + 'static>(&mut self, layer: L) -> Result<(), Error> {
+        let mut layer = layer.erased();
+        let index = self.layers.len();
+
+        if let Some(last_layer) = self.layers.last() {
+            layer
+                .connect(last_layer.shape())
+                .map_err(|error| Error::IncompatibleShape {
+                    layer: index,
+                    shape: last_layer.shape(),
+                    reason: error.into_reason(),
+                })?;
+        }
+
+        self.validate_layer(index, &layer)?;
+
+        self.layers.push(layer);
+
+        return Ok(());
+    }
+
+    /// Decodes and validates a network encoded with `Network::encode`.
+    /// Networks encoded without metadata get preprocessing that scales bytes to [0, 1], and metadata describing only that.
+    /// Both formats must decode from all bytes, so truncated networks are rejected instead of losing their metadata.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let decoded: Result<(Self, usize), _> =
+            bincode::decode_from_slice(bytes, bincode::config::standard());
+
+        let network = match decoded {
+            Ok((network, size)) if size == bytes.len() => network,
+            _ => {
+                let legacy: Result<(LegacyNetwork, usize), _> =
+                    bincode::decode_from_slice(bytes, bincode::config::standard());
+
+                let legacy = match (legacy, decoded) {
+                    (Ok((legacy, size)), _) if size == bytes.len() => legacy,
+                    (_, Err(error)) => return Err(Error::Decode(error.to_string())),
+                    (_, Ok(_)) => {
+                        return Err(Error::Decode(
+                            "Unexpected bytes after the network".to_string(),
+                        ))
+                    }
+                };
+
+                // Legacy networks were trained on IDX bytes scaled to [0, 1]
+                let preprocessing = Preprocessing {
+                    transforms: vec![Transform::Scale(1.0 / 255.0)],
+                };
+
+                Self {
+                    layers: legacy.layers,
+                    cost_function: legacy.cost_function,
+                    metadata: Metadata {
+                        input_normalization: preprocessing.description(),
+                        ..Metadata::default()
+                    },
+                    preprocessing,
+                }
+            }
         };
+
+        network.validate()?;
+
+        return Ok(network);
+    }
+
+    /// Encodes the network, e.g. to write it to a file.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|error| Error::Encode(error.to_string()))
+    }
+
+    /// Checks that the layers fit together and have consistent parameters.
+    /// Should be called after deserializing a network, before using it.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.layers.is_empty() {
+            return Err(Error::MissingInputLayer);
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            self.validate_layer(index, layer)?;
+        }
+
+        return Ok(());
     }
 
-    pub fn add_layer<L: Layer + Clone + 'static>(&mut self, layer: L) {
-        self.layers.push(layer.erased());
+    /// Checks a layer at the given index against the layer before it.
+    fn validate_layer(&self, index: usize, layer: &LayerEnum) -> Result<(), Error> {
+        match (index, layer) {
+            (0, LayerEnum::Input(_)) => {}
+            (0, _) => return Err(Error::MissingInputLayer),
+            (_, LayerEnum::Input(_)) => return Err(Error::UnexpectedInputLayer { layer: index }),
+            (_, _) => {
+                let actual = self.layers[index - 1].size();
+
+                if let Some(expected) = layer.input_size() {
+                    if expected != actual {
+                        return Err(Error::SizeMismatch {
+                            layer: index,
+                            expected,
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+
+        return layer.validate().map_err(|error| Error::InvalidLayer {
+            layer: index,
+            reason: error.into_reason(),
+        });
     }
 
     pub fn shape(&self) -> Vec<usize> {
         return self.layers.iter().map(|l| l.size()).collect();
     }
 
+    /// The average cost of the network over the samples, without any regularization penalty.
+    pub fn evaluate_loss(&self, data: &[(Vec<Float>, Vec<Float>)]) -> Float {
+        if data.is_empty() {
+            return 0.0;
+        }
+
+        let total: Float = data
+            .iter()
+            .map(|(input, expected_output)| {
+                let output = DVector::from_vec(self.feed_forward(input.clone()));
+
+                self.cost_function
+                    .function(&output, &DVector::from_column_slice(expected_output))
+                    .sum()
+            })
+            .sum();
+
+        return total / data.len() as Float;
+    }
+
+    /// Applies the preprocessing to a raw input and feeds it forward.
+    /// Use `feed_forward` for inputs that are already preprocessed, e.g. training data.
+    /// Panics if the input doesn't fit the input layer, use `try_predict` for inputs that aren't checked yet.
+    pub fn predict(&self, input: Vec<Float>) -> Vec<Float> {
+        return self.feed_forward(self.preprocessing.transform(input));
+    }
+
+    /// Like `predict`, but returns an error if the input doesn't fit the input layer.
+    pub fn try_predict(&self, input: Vec<Float>) -> Result<Vec<Float>, Error> {
+        self.check_input(&input)?;
+
+        return Ok(self.predict(input));
+    }
+
+    /// Checks that a raw input fits the input layer, before any layer is fed with it.
+    pub fn check_input(&self, input: &[Float]) -> Result<(), Error> {
+        let expected = *self.shape().first().unwrap_or(&0);
+
+        if input.len() != expected {
+            return Err(Error::SizeMismatch {
+                layer: 0,
+                expected,
+                actual: input.len(),
+            });
+        }
+
+        return Ok(());
+    }
+
     pub fn feed_forward(&self, input: Vec<Float>) -> Vec<Float> {
         let mut activation = DVector::from_vec(input) as DVector<Float>;
 
@@ -37,4 +220,276 @@ impl Network {
 
         return activation.data.into();
     }
+
+    /// Feeds every input forward, e.g. to predict a whole dataset
+    pub fn feed_forward_batch(&self, inputs: &[Vec<Float>]) -> Vec<Vec<Float>> {
+        return inputs
+            .iter()
+            .map(|input| self.feed_forward(input.clone()))
+            .collect();
+    }
+
+    #[cfg(feature = "threads")]
+    /// Feeds every input forward, splitting the inputs equally over the threads
+    ///
+    /// *Multithreaded*
+    pub fn parallel_feed_forward_batch(
+        &self,
+        inputs: &[Vec<Float>],
+        thread_count: usize,
+    ) -> Vec<Vec<Float>> {
+        let chunk_size = inputs.len().div_ceil(thread_count.max(1)).max(1);
+
+        return thread::scope(|scope| {
+            let handles: Vec<_> = inputs
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move |_| self.feed_forward_batch(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Feed forward thread panicked"))
+                .collect()
+        })
+        .expect("Feed forward thread panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layer::{FullyConnected, Input};
+    use super::LegacyNetwork;
+    use crate::preprocessing::Transform;
+    use crate::{
+        ActivationFunction, CostFunction, Error, Float, Layer, LayerEnum, Metadata, Network,
+    };
+
+    #[test]
+    fn add_layer_size_mismatch() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        assert_eq!(
+            network.add_layer(FullyConnected::new(784, 10, ActivationFunction::Sigmoid)),
+            Err(Error::MissingInputLayer)
+        );
+
+        network.add_layer(Input::new(784)).unwrap();
+
+        assert_eq!(
+            network.add_layer(FullyConnected::new(100, 10, ActivationFunction::Sigmoid)),
+            Err(Error::SizeMismatch {
+                layer: 1,
+                expected: 100,
+                actual: 784
+            })
+        );
+        assert_eq!(network.layers.len(), 1);
+    }
+
+    #[test]
+    fn validate() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(4)).unwrap();
+        network
+            .add_layer(FullyConnected::new(4, 3, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        assert_eq!(network.validate(), Ok(()));
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.biases = layer.biases.rows(0, 2).into_owned();
+        }
+
+        let reason = "Weights are of shape (3, 4), should be (2, 4)".to_string();
+
+        assert_eq!(
+            network.layers[1].validate(),
+            Err(Error::InvalidParameter(reason.clone()))
+        );
+        assert_eq!(
+            network.validate(),
+            Err(Error::InvalidLayer { layer: 1, reason })
+        );
+
+        network.layers[1] = Input::new(4).erased();
+
+        assert_eq!(
+            network.validate(),
+            Err(Error::UnexpectedInputLayer { layer: 1 })
+        );
+    }
+
+    #[test]
+    fn try_predict() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(FullyConnected::new(2, 1, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        assert_eq!(network.try_predict(vec![0.0, 1.0]).unwrap().len(), 1);
+        assert_eq!(
+            network.try_predict(vec![0.0, 1.0, 2.0]),
+            Err(Error::SizeMismatch {
+                layer: 0,
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn evaluate_loss() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(1)).unwrap();
+        network
+            .add_layer(FullyConnected::new(1, 1, ActivationFunction::Identity))
+            .unwrap();
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.weights[0] = 2.0;
+            layer.biases[0] = 0.0;
+        }
+
+        let data = vec![(vec![1.0], vec![2.0]), (vec![1.0], vec![4.0])];
+
+        // Costs of 0 and 2^2 / 2
+        assert_eq!(network.evaluate_loss(&data), 1.0);
+        assert_eq!(network.evaluate_loss(&[]), 0.0);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn parallel_feed_forward_batch() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(FullyConnected::new(2, 3, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        let inputs: Vec<Vec<Float>> = (0..7).map(|i| vec![i as Float, 1.0]).collect();
+
+        assert_eq!(
+            network.parallel_feed_forward_batch(&inputs, 3),
+            network.feed_forward_batch(&inputs)
+        );
+        assert!(network.parallel_feed_forward_batch(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn decode() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(FullyConnected::new(2, 2, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        let encoded = network.encode().unwrap();
+        let decoded = Network::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.shape(), vec![2, 2]);
+        assert!(matches!(
+            Network::decode(&encoded[..encoded.len() / 2]),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn decode_invalid_layer() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(4)).unwrap();
+        network
+            .add_layer(FullyConnected::new(4, 3, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.weights = layer.weights.rows(0, 2).into_owned();
+        }
+
+        assert!(matches!(
+            Network::decode(&network.encode().unwrap()),
+            Err(Error::InvalidLayer { layer: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network.metadata.class_names = vec!["a".to_string(), "b".to_string()];
+        network.metadata.image_dimensions = Some((2, 1));
+        network.metadata.preset = Some("mnist".to_string());
+        network.preprocessing.transforms = vec![Transform::Scale(2.0)];
+
+        let decoded = Network::decode(&network.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded.metadata, network.metadata);
+        assert_eq!(decoded.predict(vec![1.0, 2.0]), vec![2.0, 4.0]);
+        assert_eq!(decoded.metadata.class_name(1), "b");
+        assert_eq!(decoded.metadata.class_name(2), "2");
+
+        // Networks encoded before metadata was added
+        let legacy = bincode::encode_to_vec(
+            LegacyNetwork {
+                layers: network.layers.clone(),
+                cost_function: CostFunction::MeanSquaredError,
+            },
+            bincode::config::standard(),
+        )
+        .unwrap();
+
+        let decoded = Network::decode(&legacy).unwrap();
+
+        assert_eq!(decoded.shape(), vec![2]);
+        assert_eq!(
+            decoded.preprocessing.transforms,
+            vec![Transform::Scale(1.0 / 255.0)]
+        );
+        assert_eq!(
+            decoded.metadata,
+            Metadata {
+                input_normalization: decoded.preprocessing.description(),
+                ..Metadata::default()
+            }
+        );
+    }
+
+    #[test]
+    fn decode_truncated_metadata() {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network.metadata.description = Some("A network with metadata".to_string());
+        network.metadata.preset = Some("mnist".to_string());
+
+        let encoded = network.encode().unwrap();
+        let legacy = bincode::encode_to_vec(
+            LegacyNetwork {
+                layers: network.layers.clone(),
+                cost_function: CostFunction::MeanSquaredError,
+            },
+            bincode::config::standard(),
+        )
+        .unwrap();
+
+        // Truncated within the metadata, after the part shared with the legacy format
+        for length in legacy.len() + 1..encoded.len() {
+            assert!(matches!(
+                Network::decode(&encoded[..length]),
+                Err(Error::Decode(_))
+            ));
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+
+        assert!(matches!(Network::decode(&trailing), Err(Error::Decode(_))));
+    }
 }
@@ -0,0 +1,213 @@
+use crate::Float;
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A fitted transform of the inputs of a network
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Transform {
+    /// Multiplies every input by the factor
+    Scale(Float),
+    /// Subtracts the mean and divides by the standard deviation of every feature
+    Standardize { mean: Vec<Float>, std: Vec<Float> },
+    /// Subtracts the mean input, e.g. the mean image
+    SubtractMean(Vec<Float>),
+    /// Clamps every input to [min, max]
+    Clip { min: Float, max: Float },
+}
+
+impl Transform {
+    pub fn apply(&self, input: &mut [Float]) {
+        match self {
+            Self::Scale(factor) => input.iter_mut().for_each(|x| *x *= factor),
+            Self::Standardize { mean, std } => {
+                for ((x, mean), std) in input.iter_mut().zip(mean).zip(std) {
+                    *x = (*x - mean) / std;
+                }
+            }
+            Self::SubtractMean(mean) => {
+                for (x, mean) in input.iter_mut().zip(mean) {
+                    *x -= mean;
+                }
+            }
+            Self::Clip { min, max } => input.iter_mut().for_each(|x| *x = x.clamp(*min, *max)),
+        }
+    }
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Scale(factor) => write!(f, "scale({})", factor),
+            Self::Standardize { mean, .. } => write!(f, "standardize({} features)", mean.len()),
+            Self::SubtractMean(mean) => write!(f, "mean({} features)", mean.len()),
+            Self::Clip { min, max } => write!(f, "clip({}, {})", min, max),
+        }
+    }
+}
+
+/// A transform before it's fitted on training data, parsed from e.g. `standardize` or `clip:-3:3`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    Scale(Float),
+    Standardize,
+    SubtractMean,
+    Clip { min: Float, max: Float },
+}
+
+impl FromStr for Normalization {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parameters: Vec<&str> = s.split(':').collect();
+        let parse = |x: &str| x.parse::<Float>().map_err(|_| ());
+
+        match parameters.as_slice() {
+            ["scale", factor] => Ok(Self::Scale(parse(factor)?)),
+            ["standardize"] => Ok(Self::Standardize),
+            ["mean"] => Ok(Self::SubtractMean),
+            ["clip", min, max] => Ok(Self::Clip {
+                min: parse(min)?,
+                max: parse(max)?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Transforms that are applied in order to every input before it's fed forward, see `Network::predict`.
+/// Stored with the network, so inference applies exactly the transforms the network was trained with.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preprocessing {
+    pub transforms: Vec<Transform>,
+}
+
+impl Preprocessing {
+    /// Fits the normalizations on the inputs in order, every normalization is fitted on the output of the previous ones.
+    pub fn fit(normalizations: &[Normalization], inputs: &[Vec<Float>]) -> Self {
+        let mut inputs = inputs.to_vec();
+        let mut preprocessing = Self::default();
+
+        for normalization in normalizations {
+            let transform = match normalization {
+                Normalization::Scale(factor) => Transform::Scale(*factor),
+                Normalization::Standardize => {
+                    let mean = mean(&inputs);
+                    let std = (0..mean.len())
+                        .map(|i| {
+                            let variance = inputs
+                                .iter()
+                                .map(|input| (input[i] - mean[i]).powi(2))
+                                .sum::<Float>()
+                                / inputs.len().max(1) as Float;
+
+                            // Constant features are only centered
+                            if variance > 0.0 {
+                                variance.sqrt()
+                            } else {
+                                1.0
+                            }
+                        })
+                        .collect();
+
+                    Transform::Standardize { mean, std }
+                }
+                Normalization::SubtractMean => Transform::SubtractMean(mean(&inputs)),
+                Normalization::Clip { min, max } => Transform::Clip {
+                    min: *min,
+                    max: *max,
+                },
+            };
+
+            for input in &mut inputs {
+                transform.apply(input);
+            }
+
+            preprocessing.transforms.push(transform);
+        }
+
+        return preprocessing;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// A readable description of the transforms, e.g. for `Metadata::input_normalization`, or None without transforms.
+    pub fn description(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let transforms: Vec<String> = self.transforms.iter().map(|t| t.to_string()).collect();
+
+        return Some(transforms.join(", "));
+    }
+
+    /// Applies all transforms to the input
+    pub fn transform(&self, mut input: Vec<Float>) -> Vec<Float> {
+        for transform in &self.transforms {
+            transform.apply(&mut input);
+        }
+
+        return input;
+    }
+}
+
+/// The mean of every feature of the inputs
+fn mean(inputs: &[Vec<Float>]) -> Vec<Float> {
+    let mut mean = vec![0.0; inputs.first().map_or(0, |input| input.len())];
+
+    for input in inputs {
+        for (mean, x) in mean.iter_mut().zip(input) {
+            *mean += x;
+        }
+    }
+
+    return mean
+        .into_iter()
+        .map(|x| x / inputs.len() as Float)
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Normalization, Preprocessing, Transform};
+
+    #[test]
+    fn fit() {
+        let inputs = vec![vec![1.0, 5.0], vec![3.0, 5.0]];
+        let normalizations: Vec<Normalization> = ["scale:2", "standardize", "clip:-0.5:0.5"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let preprocessing = Preprocessing::fit(&normalizations, &inputs);
+
+        assert_eq!(
+            preprocessing.transforms[1],
+            Transform::Standardize {
+                mean: vec![4.0, 10.0],
+                std: vec![2.0, 1.0]
+            }
+        );
+        assert_eq!(preprocessing.transform(vec![1.0, 5.0]), vec![-0.5, 0.0]);
+        assert_eq!(preprocessing.transform(vec![2.5, 6.0]), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn subtract_mean() {
+        let preprocessing =
+            Preprocessing::fit(&[Normalization::SubtractMean], &[vec![0.0], vec![1.0]]);
+
+        assert_eq!(preprocessing.transform(vec![1.0]), vec![0.5]);
+    }
+
+    #[test]
+    fn parse() {
+        for normalization in ["scale", "clip:1", "standardize:1", "normalize"] {
+            assert!(normalization.parse::<Normalization>().is_err());
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::Float;
+
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+
+/// L1 and L2 weight penalties, added to the cost of a layer.
+/// The penalty is l1 * sum(|w|) + l2 / 2 * sum(w^2), biases are not penalized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Regularization {
+    pub l1: Float,
+    pub l2: Float,
+}
+
+impl Regularization {
+    pub fn new(l1: Float, l2: Float) -> Self {
+        Self { l1, l2 }
+    }
+
+    /// Whether both penalties are zero
+    pub fn is_none(&self) -> bool {
+        self.l1 == 0.0 && self.l2 == 0.0
+    }
+
+    /// The penalty for the given weight values
+    pub fn loss(&self, weights: &[&[Float]]) -> Float {
+        if self.is_none() {
+            return 0.0;
+        }
+
+        weights
+            .iter()
+            .flat_map(|w| w.iter())
+            .map(|w| self.l1 * w.abs() + self.l2 / 2.0 * w * w)
+            .sum()
+    }
+
+    /// The gradient of the penalty with respect to the weights
+    pub fn gradient(&self, weights: &DMatrix<Float>) -> DMatrix<Float> {
+        weights.map(|w| {
+            let sign = if w > 0.0 {
+                1.0
+            } else if w < 0.0 {
+                -1.0
+            } else {
+                0.0
+            };
+
+            self.l1 * sign + self.l2 * w
+        })
+    }
+}
+
+/// Limits the gradients before they are applied, to prevent exploding gradients
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GradientClipping {
+    /// Clamps every gradient value to [-value, value]
+    Value(Float),
+    /// Scales the gradients of all layers down when their combined L2 norm exceeds the maximum
+    Norm(Float),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, GradientClipping, Regularization};
+    use crate::layer::{FullyConnected, Input};
+    use crate::{ActivationFunction, CostFunction, LayerEnum, Network, TrainingOptions};
+    use nalgebra::{DMatrix, DVector};
+
+    fn linear_network() -> Network {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(FullyConnected::new(2, 1, ActivationFunction::Identity))
+            .unwrap();
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.weights = DMatrix::from_row_slice(1, 2, &[1.0, -2.0]);
+            layer.biases = DVector::zeros(1);
+        }
+
+        return network;
+    }
+
+    fn assert_parameters(network: &Network, expected: [Float; 3]) {
+        let parameters = parameters(network);
+
+        assert!(parameters
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| (a - b).abs() < 1e-9));
+    }
+
+    fn parameters(network: &Network) -> Vec<Float> {
+        match &network.layers[1] {
+            LayerEnum::FullyConnected(layer) => layer
+                .weights
+                .iter()
+                .chain(layer.biases.iter())
+                .copied()
+                .collect(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn loss_and_gradient() {
+        let regularization = Regularization::new(0.1, 0.5);
+        let weights = DMatrix::from_row_slice(1, 3, &[2.0, 0.0, -1.0]);
+
+        assert!((regularization.loss(&[weights.as_slice()]) - (0.3 + 1.25)).abs() < 1e-9);
+        assert_eq!(
+            regularization.gradient(&weights),
+            DMatrix::from_row_slice(1, 3, &[1.1, 0.0, -0.6])
+        );
+    }
+
+    #[test]
+    fn weight_decay() {
+        let mut network = linear_network();
+        let mut options = TrainingOptions::new(0.1);
+        options.regularization = Regularization::new(0.0, 0.5);
+
+        // A zero input and matching output leave only the penalty gradient
+        network.stochastic_gradient_descent_with_options(
+            vec![(vec![0.0, 0.0], vec![0.0])],
+            1,
+            &options,
+        );
+
+        assert_parameters(&network, [0.95, -1.9, 0.0]);
+        assert!(
+            (network.regularization_loss(&options) - 0.25 * (0.95 * 0.95 + 1.9 * 1.9)).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn clipping() {
+        let data = vec![(vec![1.0, 1.0], vec![100.0])];
+
+        let mut options = TrainingOptions::new(1.0);
+        options.clipping = Some(GradientClipping::Norm(0.5));
+
+        let mut network = linear_network();
+        network.stochastic_gradient_descent_with_options(data.clone(), 1, &options);
+
+        let step: Float = parameters(&network)
+            .iter()
+            .zip([1.0, -2.0, 0.0])
+            .map(|(a, b)| (a - b).powi(2))
+            .sum();
+
+        assert!((step.sqrt() - 0.5).abs() < 1e-9);
+
+        options.clipping = Some(GradientClipping::Value(0.1));
+
+        let mut network = linear_network();
+        network.stochastic_gradient_descent_with_options(data, 1, &options);
+
+        assert_parameters(&network, [1.1, -1.9, 0.1]);
+    }
+}
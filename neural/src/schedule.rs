@@ -0,0 +1,300 @@
+use crate::Float;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Learning rate schedule, changing the base learning rate per epoch
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum LearningRateSchedule {
+    /// Uses the base learning rate for every epoch
+    #[default]
+    Constant,
+    /// Multiplies the learning rate by the factor every `step` epochs
+    StepDecay { step: usize, factor: Float },
+    /// Multiplies the learning rate by gamma every epoch
+    Exponential { gamma: Float },
+    /// Cosine annealing from the base learning rate to the minimum over a period of epochs.
+    /// The learning rate restarts at the base after every period, which is multiplied by the multiplier (SGDR).
+    CosineAnnealing {
+        period: usize,
+        multiplier: usize,
+        min: Float,
+    },
+    /// Linearly increases the learning rate to the base over the warmup epochs, then continues with the schedule.
+    LinearWarmup {
+        epochs: usize,
+        schedule: Box<LearningRateSchedule>,
+    },
+    /// Increases the learning rate from base / 25 to the base during the warmup fraction of the epochs,
+    /// then anneals it to base / 25 000 at the last epoch.
+    OneCycle { epochs: usize, warmup: Float },
+    /// Multiplies the learning rate by the factor when the validation loss hasn't improved for `patience` epochs.
+    ReduceOnPlateau {
+        factor: Float,
+        patience: usize,
+        min: Float,
+    },
+}
+
+impl LearningRateSchedule {
+    /// The learning rate for an epoch, starting at 0.
+    /// The plateau scale is the factor by which reduce on plateau has reduced the learning rate so far.
+    fn learning_rate(&self, base: Float, epoch: usize, plateau_scale: Float) -> Float {
+        match self {
+            Self::Constant => base,
+            Self::StepDecay { step, factor } => base * factor.powi((epoch / step.max(&1)) as i32),
+            Self::Exponential { gamma } => base * gamma.powi(epoch as i32),
+            Self::CosineAnnealing {
+                period,
+                multiplier,
+                min,
+            } => {
+                let mut epoch = epoch;
+                let mut period = *period.max(&1);
+
+                while epoch >= period {
+                    epoch -= period;
+                    period *= multiplier.max(&1);
+                }
+
+                cosine(base, *min, epoch as Float / period as Float)
+            }
+            Self::LinearWarmup { epochs, schedule } => {
+                if epoch < *epochs {
+                    base * (epoch + 1) as Float / (epochs + 1) as Float
+                } else {
+                    schedule.learning_rate(base, epoch - epochs, plateau_scale)
+                }
+            }
+            Self::OneCycle { epochs, warmup } => {
+                let initial = base / 25.0;
+                let warmup_epochs = ((*epochs as Float * warmup).round() as usize).max(1);
+
+                if epoch < warmup_epochs {
+                    cosine(initial, base, epoch as Float / warmup_epochs as Float)
+                } else {
+                    let annealing_epochs = epochs.saturating_sub(warmup_epochs + 1).max(1);
+                    let progress = (epoch - warmup_epochs) as Float / annealing_epochs as Float;
+
+                    cosine(base, initial / 1000.0, progress.min(1.0))
+                }
+            }
+            Self::ReduceOnPlateau { min, .. } => (base * plateau_scale).max(*min),
+        }
+    }
+
+    /// The reduce on plateau schedule, possibly after a warmup
+    fn plateau(&self) -> Option<(Float, usize)> {
+        match self {
+            Self::ReduceOnPlateau {
+                factor, patience, ..
+            } => Some((*factor, *patience)),
+            Self::LinearWarmup { schedule, .. } => schedule.plateau(),
+            _ => None,
+        }
+    }
+}
+
+/// Interpolates from start to end along half a cosine, with progress from 0 to 1.
+fn cosine(start: Float, end: Float, progress: Float) -> Float {
+    end + (start - end) * (1.0 + (std::f64::consts::PI as Float * progress).cos()) / 2.0
+}
+
+impl FromStr for LearningRateSchedule {
+    type Err = ();
+
+    /// Parses a schedule as [name]:[parameters], e.g. `step:10:0.5` or `warmup:5:cosine:10`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, parameters) = s.split_once(':').unwrap_or((s, ""));
+
+        if name == "warmup" {
+            let (epochs, schedule) = parameters
+                .split_once(':')
+                .unwrap_or((parameters, "constant"));
+
+            return Ok(Self::LinearWarmup {
+                epochs: epochs.parse().map_err(|_| ())?,
+                schedule: Box::new(schedule.parse()?),
+            });
+        }
+
+        let parameters = parameters
+            .split(':')
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse::<Float>().map_err(|_| ()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let count = |x: Float| {
+            if x >= 0.0 && x.fract() == 0.0 {
+                Ok(x as usize)
+            } else {
+                Err(())
+            }
+        };
+
+        match (name, parameters.as_slice()) {
+            ("constant", []) => Ok(Self::Constant),
+            ("step", [step, factor]) => Ok(Self::StepDecay {
+                step: count(*step)?,
+                factor: *factor,
+            }),
+            ("exponential", [gamma]) => Ok(Self::Exponential { gamma: *gamma }),
+            ("cosine", [period, rest @ ..]) if rest.len() <= 2 => Ok(Self::CosineAnnealing {
+                period: count(*period)?,
+                multiplier: count(*rest.first().unwrap_or(&1.0))?,
+                min: *rest.get(1).unwrap_or(&0.0),
+            }),
+            ("one-cycle", [epochs, rest @ ..]) if rest.len() <= 1 => Ok(Self::OneCycle {
+                epochs: count(*epochs)?,
+                warmup: *rest.first().unwrap_or(&0.3),
+            }),
+            ("plateau", [factor, patience, rest @ ..]) if rest.len() <= 1 => {
+                Ok(Self::ReduceOnPlateau {
+                    factor: *factor,
+                    patience: count(*patience)?,
+                    min: *rest.first().unwrap_or(&0.0),
+                })
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+/// Keeps track of the learning rate during training.
+/// Validation losses are reported with `observe`, which drives reduce on plateau schedules.
+#[derive(Clone, Debug)]
+pub struct LearningRateScheduler {
+    pub schedule: LearningRateSchedule,
+    pub base_learning_rate: Float,
+    plateau_scale: Float,
+    best_loss: Option<Float>,
+    bad_epochs: usize,
+}
+
+impl LearningRateScheduler {
+    pub fn new(schedule: LearningRateSchedule, base_learning_rate: Float) -> Self {
+        Self {
+            schedule,
+            base_learning_rate,
+            plateau_scale: 1.0,
+            best_loss: None,
+            bad_epochs: 0,
+        }
+    }
+
+    /// The learning rate for an epoch, starting at 0.
+    pub fn learning_rate(&self, epoch: usize) -> Float {
+        self.schedule
+            .learning_rate(self.base_learning_rate, epoch, self.plateau_scale)
+    }
+
+    /// Whether the schedule needs validation losses to be reported with `observe`.
+    pub fn needs_validation(&self) -> bool {
+        self.schedule.plateau().is_some()
+    }
+
+    /// Reports the validation loss after an epoch, where lower is better.
+    pub fn observe(&mut self, loss: Float) {
+        let (factor, patience) = match self.schedule.plateau() {
+            Some(plateau) => plateau,
+            None => return,
+        };
+
+        if self.best_loss.is_none_or(|best| loss < best) {
+            self.best_loss = Some(loss);
+            self.bad_epochs = 0;
+        } else {
+            self.bad_epochs += 1;
+
+            if self.bad_epochs > patience {
+                self.plateau_scale *= factor;
+                self.bad_epochs = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, LearningRateSchedule, LearningRateScheduler};
+
+    fn learning_rates(schedule: &str, epochs: usize) -> Vec<Float> {
+        let scheduler = LearningRateScheduler::new(schedule.parse().unwrap(), 1.0);
+
+        (0..epochs).map(|i| scheduler.learning_rate(i)).collect()
+    }
+
+    #[test]
+    fn step_decay() {
+        assert_eq!(
+            learning_rates("step:2:0.5", 5),
+            vec![1.0, 1.0, 0.5, 0.5, 0.25]
+        );
+    }
+
+    #[test]
+    fn cosine_restarts() {
+        let rates = learning_rates("cosine:2:2", 7);
+
+        // Periods of 2 and 4 epochs, restarting at the base learning rate
+        assert_eq!(rates[0], 1.0);
+        assert!((rates[1] - 0.5).abs() < 1e-9);
+        assert_eq!(rates[2], 1.0);
+        assert!((rates[4] - 0.5).abs() < 1e-9);
+        assert_eq!(rates[6], 1.0);
+    }
+
+    #[test]
+    fn warmup() {
+        assert_eq!(
+            learning_rates("warmup:3:exponential:0.5", 5),
+            vec![0.25, 0.5, 0.75, 1.0, 0.5]
+        );
+    }
+
+    #[test]
+    fn one_cycle() {
+        let rates = learning_rates("one-cycle:10:0.3", 10);
+        let peak = rates
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+
+        assert!((rates[0] - 1.0 / 25.0).abs() < 1e-9);
+        assert_eq!(peak.0, 3);
+        assert!((peak.1 - 1.0).abs() < 1e-9);
+        assert!((rates[9] - 1.0 / 25_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reduce_on_plateau() {
+        let mut scheduler = LearningRateScheduler::new(
+            LearningRateSchedule::ReduceOnPlateau {
+                factor: 0.5,
+                patience: 1,
+                min: 0.2,
+            },
+            1.0,
+        );
+
+        for loss in [1.0, 0.8, 0.9, 0.85] {
+            scheduler.observe(loss);
+        }
+
+        assert_eq!(scheduler.learning_rate(4), 0.5);
+
+        for loss in [0.9, 0.9, 0.9, 0.9] {
+            scheduler.observe(loss);
+        }
+
+        assert_eq!(scheduler.learning_rate(8), 0.2);
+    }
+
+    #[test]
+    fn invalid() {
+        for schedule in ["step:2", "cosine:1.5", "plateau", "warmup:x", "linear"] {
+            assert!(schedule.parse::<LearningRateSchedule>().is_err());
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::layer::Shape;
+use crate::{ActivationFunction, Float, Layer, Metadata, Network};
+
+use nalgebra::DVector;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Overview of the layers and parameters of a network, see `Network::summary`.
+#[derive(Clone, Debug, Serialize)]
+pub struct NetworkSummary {
+    pub cost_function: String,
+    pub parameter_count: usize,
+    pub layers: Vec<LayerSummary>,
+    /// The preprocessing transforms, in order
+    pub preprocessing: Vec<String>,
+    pub metadata: Metadata,
+}
+
+/// Overview of a single layer of a network
+#[derive(Clone, Debug, Serialize)]
+pub struct LayerSummary {
+    pub layer_type: String,
+    pub activation: Option<String>,
+    /// The output shape of the previous layer, None for the input layer
+    pub input_shape: Option<Shape>,
+    pub output_shape: Shape,
+    pub parameter_count: usize,
+    /// Statistics of the weights, None if the layer has no weights
+    pub weights: Option<WeightStatistics>,
+    /// Fraction of ReLU units that output zero for every sample input.
+    /// None if the layer doesn't use ReLU or no sample was given.
+    pub dead_units: Option<Float>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WeightStatistics {
+    pub mean: Float,
+    pub std: Float,
+    pub min: Float,
+    pub max: Float,
+}
+
+impl WeightStatistics {
+    /// Calculates the statistics of the values, or None if there are no values.
+    pub fn new(values: &[&[Float]]) -> Option<Self> {
+        let count = values.iter().map(|v| v.len()).sum::<usize>();
+
+        if count == 0 {
+            return None;
+        }
+
+        let values = || values.iter().flat_map(|v| v.iter().copied());
+
+        let mean = values().sum::<Float>() / count as Float;
+        let variance = values().map(|x| (x - mean).powi(2)).sum::<Float>() / count as Float;
+
+        return Some(Self {
+            mean,
+            std: variance.sqrt(),
+            min: values().fold(Float::INFINITY, Float::min),
+            max: values().fold(Float::NEG_INFINITY, Float::max),
+        });
+    }
+}
+
+impl Network {
+    /// Summarizes the type, shapes and parameters of every layer.
+    /// Dead ReLU units are found by feeding the raw sample inputs through the preprocessing and the network, pass an empty sample to skip this.
+    pub fn summary(&self, sample: &[Vec<Float>]) -> NetworkSummary {
+        let mut layers: Vec<LayerSummary> = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| LayerSummary {
+                layer_type: layer.name().to_string(),
+                activation: layer.activation_function().map(|f| f.to_string()),
+                input_shape: i.checked_sub(1).map(|i| self.layers[i].shape()),
+                output_shape: layer.shape(),
+                parameter_count: layer.parameter_count(),
+                weights: WeightStatistics::new(&layer.weight_values()),
+                dead_units: None,
+            })
+            .collect();
+
+        if !sample.is_empty() {
+            for (summary, dead_units) in layers.iter_mut().zip(self.dead_units(sample)) {
+                summary.dead_units = dead_units;
+            }
+        }
+
+        return NetworkSummary {
+            cost_function: self.cost_function.to_string(),
+            parameter_count: layers.iter().map(|l| l.parameter_count).sum(),
+            layers,
+            preprocessing: self
+                .preprocessing
+                .transforms
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            metadata: self.metadata.clone(),
+        };
+    }
+
+    /// The fraction of units of every ReLU layer that don't activate for any of the inputs.
+    fn dead_units(&self, inputs: &[Vec<Float>]) -> Vec<Option<Float>> {
+        // Whether each unit activated for any input, for the ReLU layers
+        let mut alive: Vec<Option<Vec<bool>>> = self
+            .layers
+            .iter()
+            .map(|layer| match layer.activation_function() {
+                Some(ActivationFunction::ReLU) => Some(vec![false; layer.size()]),
+                _ => None,
+            })
+            .collect();
+
+        for input in inputs {
+            let mut activation = DVector::from_vec(self.preprocessing.transform(input.clone()));
+
+            for (layer, alive) in self.layers.iter().zip(alive.iter_mut()) {
+                activation = layer.feed_forward(&activation);
+
+                if let Some(alive) = alive {
+                    for (alive, value) in alive.iter_mut().zip(activation.iter()) {
+                        *alive |= *value > 0.0;
+                    }
+                }
+            }
+        }
+
+        return alive
+            .into_iter()
+            .map(|alive| {
+                alive.map(|alive| {
+                    alive.iter().filter(|a| !**a).count() as Float / alive.len() as Float
+                })
+            })
+            .collect();
+    }
+}
+
+impl Display for NetworkSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: Option<Float>, precision: usize| match value {
+            Some(value) => format!("{:.*}", precision, value),
+            None => "-".to_string(),
+        };
+
+        let rows: Vec<[String; 11]> = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| {
+                [
+                    i.to_string(),
+                    layer.layer_type.clone(),
+                    layer.activation.clone().unwrap_or_else(|| "-".to_string()),
+                    layer
+                        .input_shape
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    layer.output_shape.to_string(),
+                    layer.parameter_count.to_string(),
+                    optional(layer.weights.map(|w| w.mean), 4),
+                    optional(layer.weights.map(|w| w.std), 4),
+                    optional(layer.weights.map(|w| w.min), 4),
+                    optional(layer.weights.map(|w| w.max), 4),
+                    optional(layer.dead_units.map(|d| d * 100.0), 1),
+                ]
+            })
+            .collect();
+
+        let header = [
+            "#",
+            "Type",
+            "Activation",
+            "Input",
+            "Output",
+            "Params",
+            "Mean",
+            "Std",
+            "Min",
+            "Max",
+            "Dead %",
+        ];
+
+        let widths: Vec<usize> = header
+            .iter()
+            .enumerate()
+            .map(|(i, h)| rows.iter().map(|r| r[i].len()).fold(h.len(), usize::max))
+            .collect();
+
+        let mut write_row = |cells: &[&str]| {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+
+            writeln!(f, "{}", line.trim_end())
+        };
+
+        write_row(&header)?;
+
+        for row in &rows {
+            write_row(&row.iter().map(|c| c.as_str()).collect::<Vec<_>>())?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Cost function: {}", self.cost_function)?;
+        write!(f, "Total parameters: {}", self.parameter_count)?;
+
+        if !self.preprocessing.is_empty() {
+            write!(f, "\nPreprocessing: {}", self.preprocessing.join(", "))?;
+        }
+
+        let metadata = &self.metadata;
+
+        if let Some(description) = &metadata.description {
+            write!(f, "\nDescription: {}", description)?;
+        }
+
+        if !metadata.class_names.is_empty() {
+            write!(f, "\nClasses: {}", metadata.class_names.join(", "))?;
+        }
+
+        if let Some((width, height)) = metadata.image_dimensions {
+            write!(f, "\nImage dimensions: {}x{}", width, height)?;
+        }
+
+        if let Some(preset) = &metadata.preset {
+            write!(f, "\nPreset: {}", preset)?;
+        }
+
+        for run in &metadata.training {
+            write!(f, "\nTrained {} epochs on {}", run.epochs, run.dataset)?;
+
+            if let Some(loss) = run.loss {
+                write!(f, ", loss {:.6}", loss)?;
+            }
+
+            if let Some(accuracy) = run.accuracy {
+                write!(f, ", accuracy {:.2}%", accuracy * 100.0)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layer::{FullyConnected, Input, Shape};
+    use crate::{ActivationFunction, CostFunction, LayerEnum, Network};
+    use nalgebra::{DMatrix, DVector};
+
+    fn network() -> Network {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(2)).unwrap();
+        network
+            .add_layer(FullyConnected::new(2, 3, ActivationFunction::ReLU))
+            .unwrap();
+        network
+            .add_layer(FullyConnected::new(3, 1, ActivationFunction::Sigmoid))
+            .unwrap();
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.weights = DMatrix::from_row_slice(3, 2, &[1.0, 0.0, 0.0, 1.0, -1.0, -1.0]);
+            layer.biases = DVector::zeros(3);
+        }
+
+        return network;
+    }
+
+    #[test]
+    fn summary() {
+        let summary = network().summary(&[]);
+
+        assert_eq!(summary.parameter_count, 9 + 4);
+        assert_eq!(summary.layers[0].input_shape, None);
+        assert_eq!(summary.layers[1].input_shape, Some(Shape::flat(2)));
+        assert_eq!(summary.layers[1].activation.as_deref(), Some("relu"));
+        assert_eq!(summary.layers[1].dead_units, None);
+
+        let weights = summary.layers[1].weights.unwrap();
+
+        assert_eq!((weights.min, weights.max), (-1.0, 1.0));
+        assert_eq!(weights.mean, 0.0);
+        assert!(summary.layers[0].weights.is_none());
+    }
+
+    #[test]
+    fn dead_units() {
+        let summary = network().summary(&[vec![1.0, 0.0], vec![0.5, 0.0]]);
+
+        // The second unit only gets the zero input and the third only negative inputs
+        assert_eq!(summary.layers[1].dead_units, Some(2.0 / 3.0));
+        assert_eq!(summary.layers[2].dead_units, None);
+    }
+}
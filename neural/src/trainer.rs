@@ -0,0 +1,558 @@
+use crate::{Float, LearningRateSchedule, LearningRateScheduler, Network, TrainingOptions};
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+use nalgebra::DVector;
+use rand::seq::SliceRandom;
+
+/// Validation metric, used to select the best network and to stop training early
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// The average cost over the validation data, lower is better
+    Loss,
+    /// The fraction of samples where the largest output matches the largest expected output, higher is better.
+    /// Single outputs are compared by rounding them to 0 or 1.
+    Accuracy,
+}
+
+impl Metric {
+    /// Evaluates the metric for the network on the given data.
+    pub fn evaluate(&self, network: &Network, data: &[(Vec<Float>, Vec<Float>)]) -> Float {
+        if *self == Self::Loss {
+            return network.evaluate_loss(data);
+        }
+
+        let correct = data
+            .iter()
+            .filter(|(input, expected_output)| {
+                let output = network.feed_forward(input.clone());
+
+                if output.len() == 1 {
+                    (output[0] >= 0.5) == (expected_output[0] >= 0.5)
+                } else {
+                    argmax(&output) == argmax(expected_output)
+                }
+            })
+            .count();
+
+        return correct as Float / data.len() as Float;
+    }
+
+    /// Whether the value is an improvement of at least min delta over the best value
+    fn improves(&self, value: Float, best: Float, min_delta: Float) -> bool {
+        match self {
+            Self::Loss => value < best - min_delta,
+            Self::Accuracy => value > best + min_delta,
+        }
+    }
+
+    /// Converts the value to a loss where lower is better, for reduce on plateau schedules
+    fn as_loss(&self, value: Float) -> Float {
+        match self {
+            Self::Loss => value,
+            Self::Accuracy => 1.0 - value,
+        }
+    }
+}
+
+fn argmax(values: &[Float]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loss => write!(f, "loss"),
+            Self::Accuracy => write!(f, "accuracy"),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loss" => Ok(Self::Loss),
+            "accuracy" => Ok(Self::Accuracy),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Stops training when the validation metric hasn't improved by at least min delta for `patience` epochs
+#[derive(Clone, Copy, Debug)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: Float,
+}
+
+/// The outcome of a single training batch
+#[derive(Clone, Copy, Debug)]
+pub struct BatchResult {
+    /// The epoch, starting at 0
+    pub epoch: usize,
+    /// The batch within the epoch, starting at 0
+    pub batch: usize,
+    /// The amount of batches in the epoch
+    pub batch_count: usize,
+    /// The loss of the batch before training on it, including the regularization penalty
+    pub loss: Float,
+}
+
+/// The outcome of a single training epoch
+#[derive(Clone, Copy, Debug)]
+pub struct EpochResult {
+    /// The epoch, starting at 0
+    pub epoch: usize,
+    pub learning_rate: Float,
+    /// The average loss of the batches
+    pub loss: Float,
+    /// The validation metric, if validation data was given
+    pub validation: Option<Float>,
+    /// The average cost over the validation data, regardless of the metric
+    pub validation_loss: Option<Float>,
+    /// Whether the validation metric is the best so far
+    pub improved: bool,
+}
+
+/// The outcome of `Trainer::train`
+#[derive(Clone, Debug)]
+pub struct TrainingResult {
+    /// The amount of epochs that were trained
+    pub epochs: usize,
+    pub stopped_early: bool,
+    /// The epoch with the best validation metric, if validation data was given
+    pub best_epoch: Option<usize>,
+    pub best_validation: Option<Float>,
+    /// The results of every trained epoch, e.g. to plot the loss curves
+    pub history: Vec<EpochResult>,
+}
+
+/// Hooks into the training loop of a `Trainer`, e.g. to report progress, log metrics or save checkpoints.
+/// Returning `ControlFlow::Break` stops the training after the current batch or epoch.
+pub trait Callback {
+    fn on_epoch_begin(&mut self, _epoch: usize, _learning_rate: Float) {}
+
+    fn on_batch_end(&mut self, _result: &BatchResult) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_epoch_end(&mut self, _network: &Network, _result: &EpochResult) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Transforms a training input, see `Trainer::set_input_transform`
+pub type InputTransform = dyn FnMut(&[Float]) -> Vec<Float>;
+
+/// Trains a network for several epochs with a learning rate schedule.
+/// With validation data, keeps a copy of the best network and optionally stops early.
+pub struct Trainer {
+    pub options: TrainingOptions,
+    pub scheduler: LearningRateScheduler,
+    pub batch_size: NonZeroUsize,
+    /// The number of batches per epoch, defaulting to all training data
+    pub batch_count: Option<usize>,
+    pub epochs: usize,
+    pub metric: Metric,
+    pub early_stopping: Option<EarlyStopping>,
+    /// Whether `train` restores the network with the best validation metric when it finishes
+    pub restore_best: bool,
+    callbacks: Vec<Box<dyn Callback>>,
+    input_transform: Option<Box<InputTransform>>,
+    epoch: usize,
+    best: Option<(usize, Float, Network)>,
+    history: Vec<EpochResult>,
+    stop_requested: bool,
+}
+
+impl Trainer {
+    pub fn new(options: TrainingOptions, batch_size: NonZeroUsize, epochs: usize) -> Self {
+        let scheduler =
+            LearningRateScheduler::new(LearningRateSchedule::Constant, options.learning_rate);
+
+        Self {
+            options,
+            scheduler,
+            batch_size,
+            batch_count: None,
+            epochs,
+            metric: Metric::Loss,
+            early_stopping: None,
+            restore_best: true,
+            callbacks: vec![],
+            input_transform: None,
+            epoch: 0,
+            best: None,
+            history: vec![],
+            stop_requested: false,
+        }
+    }
+
+    /// Adds a callback, which is called in the order callbacks were added.
+    pub fn add_callback(&mut self, callback: impl Callback + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Sets a transform that's applied to every training input at the start of every epoch, e.g. random data augmentation.
+    /// Validation inputs aren't transformed.
+    pub fn set_input_transform(&mut self, transform: impl FnMut(&[Float]) -> Vec<Float> + 'static) {
+        self.input_transform = Some(Box::new(transform));
+    }
+
+    /// Trains for the configured amount of epochs, or until early stopping or a callback stops it.
+    pub fn train(
+        &mut self,
+        network: &mut Network,
+        training_data: &[(Vec<Float>, Vec<Float>)],
+        validation_data: Option<&[(Vec<Float>, Vec<Float>)]>,
+    ) -> TrainingResult {
+        while self.epoch < self.epochs && !self.should_stop() {
+            self.train_epoch(network, training_data, validation_data);
+        }
+
+        if self.restore_best {
+            self.restore(network);
+        }
+
+        return self.result();
+    }
+
+    /// Trains a single epoch and evaluates the validation metric.
+    pub fn train_epoch(
+        &mut self,
+        network: &mut Network,
+        training_data: &[(Vec<Float>, Vec<Float>)],
+        validation_data: Option<&[(Vec<Float>, Vec<Float>)]>,
+    ) -> EpochResult {
+        let epoch = self.epoch;
+
+        self.options.learning_rate = self.scheduler.learning_rate(epoch);
+
+        for callback in &mut self.callbacks {
+            callback.on_epoch_begin(epoch, self.options.learning_rate);
+        }
+
+        let mut training_data: Vec<(DVector<Float>, DVector<Float>)> = training_data
+            .iter()
+            .map(|(input, output)| {
+                let input = match &mut self.input_transform {
+                    Some(transform) => DVector::from_vec(transform(input)),
+                    None => DVector::from_column_slice(input),
+                };
+
+                (input, DVector::from_column_slice(output))
+            })
+            .collect();
+
+        training_data.shuffle(&mut rand::thread_rng());
+
+        if let Some(batch_count) = self.batch_count {
+            training_data.truncate(self.batch_size.get() * batch_count);
+        }
+
+        let batch_count = training_data.len().div_ceil(self.batch_size.get());
+        let mut total_loss = 0.0;
+        let mut trained_batches = 0;
+
+        for (batch, samples) in training_data.chunks(self.batch_size.get()).enumerate() {
+            let loss = network.train_batch(samples, &self.options);
+
+            total_loss += loss;
+            trained_batches += 1;
+
+            let result = BatchResult {
+                epoch,
+                batch,
+                batch_count,
+                loss,
+            };
+
+            if self.notify(|callback| callback.on_batch_end(&result)) {
+                break;
+            }
+        }
+
+        let validation_data = validation_data.filter(|data| !data.is_empty());
+        let validation_loss = validation_data.map(|data| network.evaluate_loss(data));
+        let validation = match (self.metric, validation_data) {
+            (Metric::Loss, _) => validation_loss,
+            (metric, Some(data)) => Some(metric.evaluate(network, data)),
+            (_, None) => None,
+        };
+
+        let mut improved = false;
+
+        if let Some(validation) = validation {
+            self.scheduler.observe(self.metric.as_loss(validation));
+
+            let min_delta = self.early_stopping.map_or(0.0, |e| e.min_delta);
+
+            improved = match &self.best {
+                Some((_, best, _)) => self.metric.improves(validation, *best, min_delta),
+                None => true,
+            };
+
+            if improved {
+                self.best = Some((epoch, validation, network.clone()));
+            }
+        }
+
+        self.epoch += 1;
+
+        let result = EpochResult {
+            epoch,
+            learning_rate: self.options.learning_rate,
+            loss: total_loss / trained_batches.max(1) as Float,
+            validation,
+            validation_loss,
+            improved,
+        };
+
+        self.history.push(result);
+        self.notify(|callback| callback.on_epoch_end(network, &result));
+
+        return result;
+    }
+
+    /// Calls every callback, returning whether any of them requested to stop so far.
+    fn notify(&mut self, mut call: impl FnMut(&mut dyn Callback) -> ControlFlow<()>) -> bool {
+        for callback in &mut self.callbacks {
+            if call(callback.as_mut()).is_break() {
+                self.stop_requested = true;
+            }
+        }
+
+        return self.stop_requested;
+    }
+
+    /// Whether a callback requested to stop, or the validation metric hasn't improved for longer than the early stopping patience.
+    pub fn should_stop(&self) -> bool {
+        if self.stop_requested {
+            return true;
+        }
+
+        match (&self.early_stopping, &self.best) {
+            (Some(early_stopping), Some((best_epoch, _, _))) => {
+                let epochs_without_improvement = self.epoch - best_epoch - 1;
+
+                epochs_without_improvement >= early_stopping.patience
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces the network with the best network so far. Returns false if there is none.
+    pub fn restore(&self, network: &mut Network) -> bool {
+        match &self.best {
+            Some((_, _, best)) => {
+                *network = best.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// A summary of the training so far
+    pub fn result(&self) -> TrainingResult {
+        TrainingResult {
+            epochs: self.epoch,
+            stopped_early: self.epoch < self.epochs && self.should_stop(),
+            best_epoch: self.best.as_ref().map(|(epoch, _, _)| *epoch),
+            best_validation: self.best.as_ref().map(|(_, value, _)| *value),
+            history: self.history.clone(),
+        }
+    }
+}
+
+/// Shuffles the data and splits off a fraction of it, e.g. for validation.
+/// Returns the remaining data and the split off data.
+pub fn split_data<T>(mut data: Vec<T>, fraction: Float) -> (Vec<T>, Vec<T>) {
+    data.shuffle(&mut rand::thread_rng());
+
+    let count = ((data.len() as Float * fraction).round() as usize).min(data.len());
+    let split = data.split_off(data.len() - count);
+
+    return (data, split);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_data, BatchResult, Callback, EarlyStopping, EpochResult, Metric, Trainer};
+    use crate::layer::{FullyConnected, Input};
+    use crate::{ActivationFunction, CostFunction, Float, LayerEnum, Network, TrainingOptions};
+    use std::cell::RefCell;
+    use std::num::NonZeroUsize;
+    use std::ops::ControlFlow;
+    use std::rc::Rc;
+
+    fn batch_size(size: usize) -> NonZeroUsize {
+        NonZeroUsize::new(size).unwrap()
+    }
+
+    fn network() -> Network {
+        let mut network = Network::new(CostFunction::MeanSquaredError);
+
+        network.add_layer(Input::new(1)).unwrap();
+        network
+            .add_layer(FullyConnected::new(1, 1, ActivationFunction::Identity))
+            .unwrap();
+
+        return network;
+    }
+
+    #[test]
+    fn metrics() {
+        let mut network = network();
+
+        if let LayerEnum::FullyConnected(layer) = &mut network.layers[1] {
+            layer.weights[0] = 1.0;
+            layer.biases[0] = 0.0;
+        }
+
+        let data = vec![(vec![1.0], vec![1.0]), (vec![0.2], vec![1.0])];
+
+        assert!((Metric::Loss.evaluate(&network, &data) - 0.32 / 2.0).abs() < 1e-9);
+        assert_eq!(Metric::Accuracy.evaluate(&network, &data), 0.5);
+    }
+
+    #[test]
+    fn early_stopping() {
+        let data: Vec<(Vec<Float>, Vec<Float>)> = (0..10)
+            .map(|x| (vec![x as Float / 10.0], vec![0.5]))
+            .collect();
+
+        // A learning rate of 0 never improves after the first epoch
+        let mut trainer = Trainer::new(TrainingOptions::new(0.0), batch_size(5), 100);
+        trainer.early_stopping = Some(EarlyStopping {
+            patience: 2,
+            min_delta: 0.0,
+        });
+
+        let result = trainer.train(&mut network(), &data, Some(&data));
+
+        assert_eq!(result.epochs, 3);
+        assert!(result.stopped_early);
+        assert_eq!(result.best_epoch, Some(0));
+        assert_eq!(result.history.len(), 3);
+        assert!(result
+            .history
+            .iter()
+            .all(|epoch| epoch.validation == epoch.validation_loss));
+    }
+
+    #[test]
+    fn restore_best() {
+        let data = vec![(vec![1.0], vec![0.5])];
+        let mut network = network();
+
+        // A huge learning rate makes the network diverge after the first epoch
+        let mut trainer = Trainer::new(TrainingOptions::new(0.1), batch_size(1), 1);
+        trainer.train_epoch(&mut network, &data, Some(&data));
+        trainer.scheduler.base_learning_rate = 100.0;
+
+        for _ in 0..3 {
+            let result = trainer.train_epoch(&mut network, &data, Some(&data));
+
+            assert!(!result.improved);
+        }
+
+        let best = trainer.result().best_validation.unwrap();
+
+        assert!(Metric::Loss.evaluate(&network, &data) > best);
+        assert!(trainer.restore(&mut network));
+        assert_eq!(Metric::Loss.evaluate(&network, &data), best);
+    }
+
+    #[test]
+    fn callbacks() {
+        struct Recorder(Rc<RefCell<Vec<String>>>);
+
+        impl Callback for Recorder {
+            fn on_epoch_begin(&mut self, epoch: usize, _learning_rate: Float) {
+                self.0.borrow_mut().push(format!("begin {}", epoch));
+            }
+
+            fn on_batch_end(&mut self, result: &BatchResult) -> ControlFlow<()> {
+                self.0
+                    .borrow_mut()
+                    .push(format!("batch {}/{}", result.batch, result.batch_count));
+
+                if result.epoch == 1 && result.batch == 1 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+
+            fn on_epoch_end(
+                &mut self,
+                _network: &Network,
+                result: &EpochResult,
+            ) -> ControlFlow<()> {
+                assert!(result.loss > 0.0);
+                self.0.borrow_mut().push(format!("end {}", result.epoch));
+
+                ControlFlow::Continue(())
+            }
+        }
+
+        let data: Vec<(Vec<Float>, Vec<Float>)> =
+            (0..5).map(|x| (vec![x as Float], vec![10.0])).collect();
+        let events = Rc::new(RefCell::new(vec![]));
+
+        let mut trainer = Trainer::new(TrainingOptions::new(0.001), batch_size(2), 10);
+        trainer.add_callback(Recorder(events.clone()));
+
+        let result = trainer.train(&mut network(), &data, None);
+
+        assert_eq!(result.epochs, 2);
+        assert!(result.stopped_early);
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "begin 0",
+                "batch 0/3",
+                "batch 1/3",
+                "batch 2/3",
+                "end 0",
+                "begin 1",
+                "batch 0/3",
+                "batch 1/3",
+                "end 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn input_transform() {
+        let data: Vec<(Vec<Float>, Vec<Float>)> =
+            (0..4).map(|x| (vec![x as Float], vec![1.0])).collect();
+        let transformed = Rc::new(RefCell::new(0));
+        let counter = transformed.clone();
+
+        let mut trainer = Trainer::new(TrainingOptions::new(0.001), batch_size(2), 3);
+        trainer.set_input_transform(move |input| {
+            *counter.borrow_mut() += 1;
+            input.to_vec()
+        });
+        trainer.train(&mut network(), &data, Some(&data));
+
+        assert_eq!(*transformed.borrow(), 12);
+    }
+
+    #[test]
+    fn split() {
+        let (remaining, split) = split_data((0..10).collect(), 0.3);
+
+        assert_eq!((remaining.len(), split.len()), (7, 3));
+    }
+}